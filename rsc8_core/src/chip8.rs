@@ -1,4 +1,21 @@
-use crate::{error::InstructionError, instruction::Instruction};
+use alloc::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+use crate::{
+    checksum,
+    error::{EmuError, InstructionError, LoadError, StateError},
+    input::KeyScript,
+    instruction::{self, Instruction, INSTRUCTION_COUNT, INSTRUCTION_NAMES},
+    quirks::{KnownRom, Quirks},
+    rng::LinearCongruentialGenerator,
+    screen::{DrawMode, Screen},
+    time::{TimeSource, TIMER_HZ},
+};
 
 pub const MEMORY_SIZE: usize = 4096;
 pub const NUM_REGISTERS: usize = 16;
@@ -9,7 +26,12 @@ pub const ROM_START: usize = 512;
 pub const FONTSET_START: usize = 0;
 pub const FONTSET_SIZE: usize = 80;
 
+pub const MEMORY_REGION_COUNT: usize = 5;
+
 pub const KEYPAD_SIZE: usize = 16;
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+pub const DEFAULT_AUDIO_PITCH: u8 = 64; // 对应 4000Hz 的样本播放速率
+pub const DEFAULT_TONE_HZ: f32 = 440.0;
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
 
@@ -38,454 +60,3957 @@ const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
-pub struct Chip8<R>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HaltReason {
+    InfiniteLoop,       // 1NNN 跳转到自身
+    Exit,               // SCHIP 00FD
+    UnknownOpcode(u16), // 需开启 halt_on_unknown_opcode
+    StackOverflow,      // 2NNN 超出调用栈深度
+}
+
+// 前端应当播放的声音
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tone {
+    Silent,
+    Square {
+        hz: f32,
+    }, // 经典 CHIP-8 的方波
+    Pattern {
+        bytes: [u8; AUDIO_PATTERN_SIZE],
+        pitch: u8,
+    }, // XO-CHIP 样本缓冲区和音高
+}
+
+// 单步执行后的 CPU 快照
+pub struct CpuState {
+    pub pc: u16,
+    pub i: u16,
+    pub v: [u8; NUM_REGISTERS],
+    pub sp: u16,
+    pub dt: u8,
+    pub st: u8,
+    pub executed: Option<Instruction>, // 已停机时不执行任何指令, 为 None
+}
+
+// 一条指令执行前的寄存器与将被覆盖的内存, 用于 undo_last_tick
+struct JournalEntry {
+    pc: u16,
+    i_reg: u16,
+    v_reg: [u8; NUM_REGISTERS],
+    stack_pointer: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+    halted: bool,
+    halt_reason: Option<HaltReason>,
+    selected_planes: u8,
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    audio_pitch: u8,
+    stack_slot: Option<(usize, u16)>, // 2NNN 将覆盖的 (下标, 旧值)
+    memory: Vec<(u16, u8)>,           // (地址, 旧值)
+}
+
+const STATE_MAGIC: &[u8; 4] = b"RSC8";
+const STATE_VERSION: u8 = 2;
+
+// 按顺序读取存档, 多字节字段均为大端序
+struct StateReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or(StateError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+// 按位打包屏幕, 高位在前, 索引与 get_display 相同
+fn pack_screen<const W: usize, const H: usize>(screen: &Screen<W, H>, buf: &mut Vec<u8>) {
+    let mut byte = 0u8;
+    for (idx, on) in screen.pixels().enumerate() {
+        byte |= (on as u8) << (7 - idx % 8);
+        if idx % 8 == 7 {
+            buf.push(byte);
+            byte = 0;
+        }
+    }
+    if !(W * H).is_multiple_of(8) {
+        buf.push(byte);
+    }
+}
+
+fn unpack_screen<const W: usize, const H: usize>(screen: &mut Screen<W, H>, pixels: &[u8]) {
+    for y in 0..H {
+        for x in 0..W {
+            let idx = x + y * W;
+            screen.set(x, y, pixels[idx / 8] & (0x80 >> (idx % 8)) != 0);
+        }
+    }
+}
+
+// 存档中的停机原因: 标记字节和 UnknownOpcode 的操作码
+fn encode_halt_reason(reason: Option<HaltReason>) -> (u8, u16) {
+    match reason {
+        None => (0, 0),
+        Some(HaltReason::InfiniteLoop) => (1, 0),
+        Some(HaltReason::Exit) => (2, 0),
+        Some(HaltReason::UnknownOpcode(opcode)) => (3, opcode),
+        Some(HaltReason::StackOverflow) => (4, 0),
+    }
+}
+
+fn decode_halt_reason(tag: u8, opcode: u16) -> Result<Option<HaltReason>, StateError> {
+    Ok(match tag {
+        0 => None,
+        1 => Some(HaltReason::InfiniteLoop),
+        2 => Some(HaltReason::Exit),
+        3 => Some(HaltReason::UnknownOpcode(opcode)),
+        4 => Some(HaltReason::StackOverflow),
+        _ => return Err(StateError::InvalidHaltReason(tag)),
+    })
+}
+
+// 百位, 十位, 个位
+fn bcd(value: u8) -> [u8; 3] {
+    [value / 100, (value / 10) % 10, value % 10]
+}
+
+// 一次 tick 产生的副作用
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickResult {
+    pub drew: bool,            // 屏幕有更新
+    pub beep: bool,            // 声音计时器非零
+    pub awaiting_key: bool,    // FX0A 正在等待按键
+    pub awaiting_vblank: bool, // display_wait 下 DXYN 等待下一帧
+    pub halted: bool,
+    pub self_modified: bool, // 写入了 ROM 自身 (需开启 warn_self_modify)
+    pub runaway: bool,       // 超过 runaway_threshold 条指令没有绘制或计时器更新, 已自动暂停
+}
+
+// 分辨率在编译期确定, 默认为经典的 64x32
+pub struct Chip8<R, const W: usize = SCREEN_WIDTH, const H: usize = SCREEN_HEIGHT>
 where
     R: Iterator<Item = u16>,
 {
     pub memory: [u8; MEMORY_SIZE],
     pub pc: u16,
     pub v_reg: [u8; NUM_REGISTERS],
+    frozen_mask: u16, // 第 x 位为 1 表示 Vx 被锁定为 frozen_values[x]
+    frozen_values: [u8; NUM_REGISTERS],
     pub i_reg: u16,
     pub delay_timer: u8,
     pub sound_timer: u8,
-    pub stack: [u16; STACK_SIZE],
+    timer_error: i64,    // update_timers 的舍入误差, 单位 1/60 毫秒
+    pub stack: Vec<u16>, // 长度即最大调用深度
     pub stack_pointer: u16,
     pub keypad: [bool; KEYPAD_SIZE],
-    pub screen: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    pub key_map: [u8; KEYPAD_SIZE], // 物理按键位置 -> CHIP-8 键值, keypress_mapped 使用
+    pub screen: Screen<W, H>,
+    pub plane1: Screen<W, H>, // XO-CHIP 第二个绘图平面, screen 为平面 0
+    pub selected_planes: u8,  // XO-CHIP 当前绘图平面的位掩码, 由 FN01 设置
     pub draw_flag: bool,
-    pub rng: R, // 随机数生成器
+    pub draw_mode: DrawMode,
+    pub turbo: bool,                          // 忽略 display_wait, 尽可能快地运行
+    frame_drawn: bool,                        // 本帧已经执行过 DXYN
+    last_draw_font: bool,                     // 最近一次 DXYN 的精灵数据来自字符集区域
+    pub track_collisions: bool,               // 是否记录碰撞像素
+    pub last_collision_pixels: Vec<(u8, u8)>, // 最近一次 DXYN 擦除的像素坐标
+    fade: Vec<u8>,                            // 余辉亮度, 首次调用 age_display 时分配
+    pub rng: R,                               // 随机数生成器
+    pub record_rng: bool,                     // 是否记录 CXNN 取得的随机数
+    rng_log: Vec<u16>,
+    rng_replay: VecDeque<u16>, // 回放队列, 非空时优先于 rng
+    pub quirks: Quirks,
+    pub xo_chip: bool, // 启用 XO-CHIP 扩展指令, 关闭时按未知操作码处理
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    audio_pitch: u8,
+    pub tone_hz: f32,                         // 非 XO-CHIP 模式下方波的频率
+    pub sys_hook: Option<fn(&mut Self, u16)>, // 0NNN 回调, 默认忽略该指令
     pub wait_for_key_release: Option<usize>,
+    key_wait_snapshot: Option<[bool; KEYPAD_SIZE]>, // FX0A 开始等待时已按下的键
+    pub rom_len: usize,
+    memory_fill: u8,                      // reset 时填充未使用内存的字节
+    pub allow_font_write: bool,           // load_at 是否允许写入字符集区域
+    pub protect_interpreter_region: bool, // FX33/FX55 写入 PROGRAM_START 以下时报错
+    pub warn_self_modify: bool,           // FX33/FX55 写入 ROM 区域时通过 TickResult 报告
+    self_modified: bool,
+    pub cycle_count: u64, // 已执行的指令数
+    pub key_script: KeyScript,
+    pub track_cycles: bool,  // 是否累计指令周期
+    pub cycles_elapsed: u64, // 已消耗的 COSMAC VIP 机器周期
+    pub profiling: bool,     // 是否统计每种指令的执行次数
+    opcode_counts: [u64; INSTRUCTION_COUNT],
+    redundant_draws: u64,
+    pub trace_depth: usize, // 保留最近多少条 (pc, opcode), 0 表示不记录
+    trace: VecDeque<(u16, u16)>,
+    pub journal_depth: usize, // undo_last_tick 最多可撤销的指令数, 0 表示不记录
+    journal: VecDeque<JournalEntry>,
+    pub paused: bool,
+    pub runaway_threshold: Option<u64>, // 疑似死循环的指令数上限, None 表示不检测
+    since_progress: u64,                // 上次 DXYN 或计时器更新以来执行的指令数
+    pub halted: bool,                   // 程序已结束(例如跳转到自身), tick 不再执行
+    pub halt_on_unknown_opcode: bool,   // 遇到未知操作码时停机并让 pc 指向它, 而不是返回错误
+    pub strict_decode: bool, // 关闭时未知尾数按 Instruction::decode_lenient 处理, 不再报错
+    halt_reason: Option<HaltReason>,
+    pub pause_timers: bool, // 暂停时是否同时冻结计时器
 }
 
-impl<R> Chip8<R>
+pub type Chip8Classic<R> = Chip8<R, SCREEN_WIDTH, SCREEN_HEIGHT>;
+// SCHIP 高分辨率模式
+pub type Chip8Super<R> = Chip8<R, 128, 64>;
+
+impl<R, const W: usize, const H: usize> Chip8<R, W, H>
 where
     R: Iterator<Item = u16>,
 {
     pub fn new(rng: R) -> Self {
+        Self::with_stack_depth(rng, STACK_SIZE)
+    }
+
+    // SCHIP 等解释器需要更深的调用栈
+    pub fn with_stack_depth(rng: R, depth: usize) -> Self {
         Self {
             memory: [0; MEMORY_SIZE],
             pc: PROGRAM_START,
             v_reg: [0; NUM_REGISTERS],
+            frozen_mask: 0,
+            frozen_values: [0; NUM_REGISTERS],
             i_reg: 0,
             delay_timer: 0,
             sound_timer: 0,
-            stack: [0; STACK_SIZE],
+            timer_error: 0,
+            stack: vec![0; depth],
             stack_pointer: 0,
             keypad: [false; KEYPAD_SIZE],
-            screen: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            key_map: core::array::from_fn(|i| i as u8),
+            screen: Screen::default(),
+            plane1: Screen::default(),
+            selected_planes: 0b01,
             draw_flag: false,
+            draw_mode: DrawMode::default(),
+            turbo: false,
+            frame_drawn: false,
+            last_draw_font: false,
+            track_collisions: false,
+            last_collision_pixels: Vec::new(),
+            fade: Vec::new(),
             rng,
+            record_rng: false,
+            rng_log: Vec::new(),
+            rng_replay: VecDeque::new(),
+            quirks: Quirks::default(),
+            xo_chip: false,
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            audio_pitch: DEFAULT_AUDIO_PITCH,
+            tone_hz: DEFAULT_TONE_HZ,
+            sys_hook: None,
             wait_for_key_release: None,
+            key_wait_snapshot: None,
+            rom_len: 0,
+            memory_fill: 0,
+            allow_font_write: false,
+            protect_interpreter_region: false,
+            warn_self_modify: false,
+            self_modified: false,
+            cycle_count: 0,
+            key_script: KeyScript::new(),
+            track_cycles: false,
+            cycles_elapsed: 0,
+            profiling: false,
+            opcode_counts: [0; INSTRUCTION_COUNT],
+            redundant_draws: 0,
+            trace_depth: 0,
+            trace: VecDeque::new(),
+            journal_depth: 0,
+            journal: VecDeque::new(),
+            paused: false,
+            runaway_threshold: None,
+            since_progress: 0,
+            halted: false,
+            halt_on_unknown_opcode: false,
+            strict_decode: true,
+            halt_reason: None,
+            pause_timers: false,
         }
     }
 
     pub fn load_fontset(&mut self) {
-        self.memory[..FONTSET.len()].copy_from_slice(&FONTSET);
+        self.reload_fontset();
     }
 
-    pub fn load_rom(&mut self, buf: &[u8]) {
-        let rom_end = ROM_START + buf.len();
-        self.memory[ROM_START..rom_end].copy_from_slice(buf);
+    // 不做完整 reset, 只恢复被 ROM 覆盖的字符集
+    pub fn reload_fontset(&mut self) {
+        self.memory[FONTSET_START..FONTSET_START + FONTSET_SIZE].copy_from_slice(&FONTSET);
     }
 
-    pub fn tick(&mut self) -> Result<(), InstructionError> {
-        let opcode = self.fetch_opcode();
-        let instruction = Instruction::try_from(opcode)?;
-        self.execute_instruction(&instruction);
+    // 字符集区域是否仍与内置字体一致
+    pub fn font_region_intact(&self) -> bool {
+        self.memory[FONTSET_START..FONTSET_START + FONTSET_SIZE] == FONTSET
+    }
+
+    pub fn load_rom(&mut self, buf: &[u8]) -> Result<(), LoadError> {
+        self.load_at(ROM_START as u16, buf)?;
+        self.rom_len = buf.len();
         Ok(())
     }
 
-    pub fn tick_timer(&mut self) {
-        self.delay_timer = self.delay_timer.saturating_sub(1);
-        self.sound_timer = self.sound_timer.saturating_sub(1);
+    // 以大端格式从 PROGRAM_START 依次写入操作码, 主要用于测试
+    // 超出内存末尾的操作码被忽略
+    pub fn load_program(&mut self, opcodes: &[u16]) {
+        let rom = &mut self.memory[ROM_START..];
+        for (dst, opcode) in rom.chunks_exact_mut(2).zip(opcodes) {
+            dst.copy_from_slice(&opcode.to_be_bytes());
+        }
+        self.rom_len = (opcodes.len() * 2).min(MEMORY_SIZE - ROM_START);
     }
 
-    pub fn keypress(&mut self, idx: usize, pressed: bool) {
-        self.keypad[idx] = pressed;
+    // 从十六进制字符串加载 ROM, 空白字符会被忽略
+    pub fn load_rom_hex(&mut self, s: &str) -> Result<(), LoadError> {
+        let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(LoadError::InvalidHex(digits.len()));
+        }
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        for (idx, pair) in digits.chunks(2).enumerate() {
+            // from_str_radix 会接受开头的 '+', 先逐个检查
+            if !pair.iter().all(u8::is_ascii_hexdigit) {
+                return Err(LoadError::InvalidHex(idx * 2));
+            }
+            let hex = core::str::from_utf8(pair).map_err(|_| LoadError::InvalidHex(idx * 2))?;
+            let byte = u8::from_str_radix(hex, 16).map_err(|_| LoadError::InvalidHex(idx * 2))?;
+            bytes.push(byte);
+        }
+        self.load_at(PROGRAM_START, &bytes)?;
+        self.rom_len = bytes.len();
+        Ok(())
     }
 
-    // |   |
-    // | h | 0xA2 -> 左移8位 0xA200
-    // |_l_| 0xF0 -> 按位或  0xA2F0
-    // Chip8 大端格式
-    fn fetch_opcode(&mut self) -> u16 {
-        let high_byte = self.memory[self.pc as usize] as u16;
-        let low_byte = self.memory[self.pc as usize + 1] as u16;
+    // 在任意地址写入数据, 可用于拼装测试框架和被测程序
+    pub fn load_at(&mut self, addr: u16, bytes: &[u8]) -> Result<(), LoadError> {
+        let start = addr as usize;
+        let end = start + bytes.len();
+        if end > MEMORY_SIZE {
+            return Err(LoadError::OutOfBounds {
+                addr,
+                len: bytes.len(),
+            });
+        }
+        if !self.allow_font_write && start < FONTSET_START + FONTSET_SIZE && !bytes.is_empty() {
+            return Err(LoadError::FontRegion(addr));
+        }
+        self.memory[start..end].copy_from_slice(bytes);
+        Ok(())
+    }
 
-        // Chip8 操作码都是 2 字节
-        self.pc += 2;
+    pub fn memory_view(&self) -> &[u8] {
+        &self.memory
+    }
 
-        (high_byte << 8) | low_byte
+    // 越界时返回 None
+    pub fn memory_range(&self, r: Range<usize>) -> Option<&[u8]> {
+        self.memory.get(r)
     }
 
-    pub fn get_display(&self) -> &[bool] {
-        &self.screen
+    // 内存布局, 用于带标签的十六进制视图
+    pub fn memory_regions(&self) -> [(Range<usize>, &'static str); MEMORY_REGION_COUNT] {
+        let i = (self.i_reg as usize).min(MEMORY_SIZE);
+        let pc = (self.pc as usize).min(MEMORY_SIZE);
+        [
+            (FONTSET_START..FONTSET_START + FONTSET_SIZE, "font"),
+            (0..PROGRAM_START as usize, "interpreter"),
+            (ROM_START..MEMORY_SIZE, "rom"),
+            (i..(i + 1).min(MEMORY_SIZE), "i"),
+            (pc..(pc + 2).min(MEMORY_SIZE), "pc"),
+        ]
     }
 
-    pub fn reset(&mut self) {
-        self.pc = PROGRAM_START;
-        self.memory = [0; MEMORY_SIZE];
-        self.screen = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
-        self.v_reg = [0; NUM_REGISTERS];
-        self.i_reg = 0;
-        self.stack_pointer = 0;
-        self.stack = [0; STACK_SIZE];
-        self.keypad = [false; KEYPAD_SIZE];
-        self.delay_timer = 0;
-        self.sound_timer = 0;
-        self.draw_flag = false;
-        self.memory[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+    // 已加载 ROM 的 CRC32, 用于识别游戏
+    pub fn rom_checksum(&self) -> u32 {
+        checksum::crc32(self.rom_bytes())
     }
 
-    fn execute_instruction(&mut self, instruction: &Instruction) {
-        match *instruction {
-            Instruction::Ins00E0 => {
-                self.screen = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
-                self.draw_flag = true;
-            }
-            Instruction::Ins00EE => {
-                self.stack_pointer -= 1;
-                self.pc = self.stack[self.stack_pointer as usize];
-            }
-            Instruction::Ins1NNN(nnn) => {
-                self.pc = nnn;
-            }
-            Instruction::Ins2NNN(nnn) => {
-                if self.stack_pointer as usize >= STACK_SIZE {
-                    panic!("2NNN failure, stack overflow");
-                }
-                self.stack[self.stack_pointer as usize] = self.pc;
-                self.stack_pointer += 1;
-                self.pc = nnn;
-            }
-            Instruction::Ins3XNN(x, nn) => {
-                if self.v_reg[x as usize] == nn {
-                    self.pc += 2;
-                }
-            }
-            Instruction::Ins4XNN(x, nn) => {
-                if self.v_reg[x as usize] != nn {
-                    self.pc += 2;
-                }
-            }
-            Instruction::Ins5XY0(x, y) => {
-                if self.v_reg[x as usize] == self.v_reg[y as usize] {
-                    self.pc += 2;
-                }
-            }
-            Instruction::Ins6XNN(x, nn) => {
-                self.v_reg[x as usize] = nn;
-            }
-            Instruction::Ins7XNN(x, nn) => {
-                // 只保留低 8 位
-                self.v_reg[x as usize] = self.v_reg[x as usize].wrapping_add(nn);
-            }
-            Instruction::Ins8XY0(x, y) => {
-                self.v_reg[x as usize] = self.v_reg[y as usize];
-            }
-            Instruction::Ins8XY1(x, y) => {
-                self.v_reg[x as usize] |= self.v_reg[y as usize];
-                self.v_reg[0xF] = 0;
-            }
-            Instruction::Ins8XY2(x, y) => {
-                self.v_reg[x as usize] &= self.v_reg[y as usize];
-                self.v_reg[0xF] = 0;
-            }
-            Instruction::Ins8XY3(x, y) => {
-                self.v_reg[x as usize] ^= self.v_reg[y as usize];
-                self.v_reg[0xF] = 0;
-            }
-            Instruction::Ins8XY4(x, y) => {
-                let (res, carry) = self.v_reg[x as usize].overflowing_add(self.v_reg[y as usize]);
-                self.v_reg[x as usize] = res;
-                self.v_reg[0xF] = carry as u8;
-            }
-            Instruction::Ins8XY5(x, y) => {
-                let (res, borrow) = self.v_reg[x as usize].overflowing_sub(self.v_reg[y as usize]);
-                self.v_reg[x as usize] = res;
-                self.v_reg[0xF] = !borrow as u8;
-            }
-            Instruction::Ins8XY6(x, y) => {
-                self.v_reg[x as usize] = self.v_reg[y as usize];
-                let dropped = self.v_reg[x as usize] & 1;
-                self.v_reg[x as usize] >>= 1;
-                self.v_reg[0xF] = dropped;
-            }
-            Instruction::Ins8XY7(x, y) => {
-                let (res, borrow) = self.v_reg[y as usize].overflowing_sub(self.v_reg[x as usize]);
-                self.v_reg[x as usize] = res;
-                self.v_reg[0xF] = !borrow as u8;
-            }
-            Instruction::Ins8XYE(x, y) => {
-                self.v_reg[x as usize] = self.v_reg[y as usize];
-                let dropped = self.v_reg[x as usize] >> 7;
-                self.v_reg[x as usize] <<= 1;
-                self.v_reg[0xF] = dropped;
-            }
-            Instruction::Ins9XY0(x, y) => {
-                if self.v_reg[x as usize] != self.v_reg[y as usize] {
-                    self.pc += 2;
-                }
-            }
-            Instruction::InsANNN(nnn) => {
-                self.i_reg = nnn;
-            }
-            Instruction::InsBNNN(nnn) => {
-                self.pc = self.v_reg[0] as u16 + nnn;
+    // 最近载入的 ROM, 反映执行过程中的自修改
+    pub fn rom_bytes(&self) -> &[u8] {
+        &self.memory[ROM_START..self.rom_end()]
+    }
+
+    // ROM 结束地址(不含), rom_len 是公开字段, 取值异常时也不能溢出
+    fn rom_end(&self) -> usize {
+        ROM_START.saturating_add(self.rom_len).min(MEMORY_SIZE)
+    }
+
+    // 根据内置的 ROM 表推荐 quirks, 未知 ROM 返回 None
+    pub fn detect_quirks(&self) -> Option<Quirks> {
+        Quirks::lookup(self.rom_checksum())
+    }
+
+    // 同 detect_quirks, 但使用调用方提供的 ROM 表
+    pub fn detect_quirks_in(&self, table: &[KnownRom]) -> Option<Quirks> {
+        Quirks::lookup_in(table, self.rom_checksum())
+    }
+
+    // 静态扫描 ROM, 返回所有无法解码的 (地址, 操作码)
+    // 数据区也会被当作指令扫描, 结果仅供参考
+    pub fn validate_rom(&self) -> Vec<(u16, u16)> {
+        (ROM_START..self.rom_end())
+            .step_by(2)
+            .map(|addr| (addr as u16, self.opcode_at(addr as u16)))
+            .filter(|&(_, opcode)| self.decode_checked(opcode).is_err())
+            .collect()
+    }
+
+    // 从 start 开始沿跳转和调用静态追踪, 返回可达的指令地址, 用于区分代码和数据
+    // 遇到返回, 退出, 无法解码的操作码时停止; BNNN 的目标依赖运行时的 V0, 不继续追踪
+    pub fn reachable_from(&self, start: u16) -> BTreeSet<u16> {
+        let mut reachable = BTreeSet::new();
+        let mut pending = vec![start];
+        while let Some(addr) = pending.pop() {
+            if addr as usize + 1 >= MEMORY_SIZE || reachable.contains(&addr) {
+                continue;
             }
-            Instruction::InsCXNN(x, nn) => {
-                let random = self.rng.next().unwrap_or_default();
-                self.v_reg[x as usize] = random as u8 & nn;
+            let Ok(instruction) = self.decode_checked(self.opcode_at(addr)) else {
+                continue;
+            };
+            reachable.insert(addr);
+            let next = addr + 2;
+            match instruction {
+                Instruction::Ins00EE | Instruction::Ins00FD | Instruction::InsBNNN(_) => {}
+                Instruction::Ins1NNN(target) => pending.push(target),
+                Instruction::Ins2NNN(target) => pending.extend([target, next]),
+                Instruction::Ins3XNN(..)
+                | Instruction::Ins4XNN(..)
+                | Instruction::Ins5XY0(..)
+                | Instruction::Ins9XY0(..)
+                | Instruction::InsEX9E(_)
+                | Instruction::InsEXA1(_) => pending.extend([next, next + 2]),
+                _ => pending.push(next),
             }
-            Instruction::InsDXYN(x, y, n) => {
-                let vx = self.v_reg[x as usize] % SCREEN_WIDTH as u8;
-                let vy = self.v_reg[y as usize] % SCREEN_HEIGHT as u8;
-                self.v_reg[0xF] = 0;
-                for row in 0..n {
-                    let screen_y = vy + row;
-                    if screen_y >= SCREEN_HEIGHT as u8 {
-                        break;
-                    }
-                    let sprite_row = self.memory[(self.i_reg + row as u16) as usize];
-                    for col in 0..8 {
-                        let screen_x = vx + col;
-                        if screen_x >= SCREEN_WIDTH as u8 {
-                            break;
-                        }
-                        // 逐位(bit)检查 判断当前像素是否是 1
-                        let sprite_pixel = (sprite_row & (0b1000_0000 >> col)) != 0;
-                        // 将二维坐标转换为一维数组索引
-                        let screen_pixel_index =
-                            screen_x as usize + screen_y as usize * SCREEN_WIDTH;
-                        let screen_pixel = self.screen[screen_pixel_index];
-                        // 碰撞检测 VF碰撞检测标志位
-                        if sprite_pixel && screen_pixel {
-                            self.v_reg[0xF] = 1;
-                        }
-                        self.screen[screen_pixel_index] ^= sprite_pixel;
-                    }
-                    self.draw_flag = true;
-                }
+        }
+        reachable
+    }
+
+    pub fn tick(&mut self) -> Result<TickResult, EmuError> {
+        if self.paused {
+            return Ok(TickResult::default());
+        }
+        if self.halted {
+            return Ok(TickResult {
+                halted: true,
+                ..TickResult::default()
+            });
+        }
+        let pc = self.pc;
+        // draw_flag 由前端清除, 这里只关心本条指令是否绘制
+        let draw_flag = core::mem::take(&mut self.draw_flag);
+        self.self_modified = false;
+        let instruction = self.run_instruction();
+        let drew = self.draw_flag;
+        self.draw_flag |= draw_flag;
+        // 只统计成功执行的指令
+        let mut runaway = false;
+        if let Ok(executed) = &instruction {
+            self.since_progress += 1;
+            if drew || matches!(executed, Instruction::InsDXYN(..)) {
+                self.since_progress = 0;
             }
-            Instruction::InsEX9E(x) => {
-                if self.keypad[self.v_reg[x as usize] as usize] {
-                    self.pc += 2;
-                }
+            runaway = self
+                .runaway_threshold
+                .is_some_and(|limit| self.since_progress >= limit);
+            if runaway {
+                self.paused = true;
+                self.since_progress = 0;
             }
-            Instruction::InsEXA1(x) => {
-                if !self.keypad[self.v_reg[x as usize] as usize] {
-                    self.pc += 2;
-                }
+        }
+        let instruction = match instruction {
+            Err(EmuError::Instruction(_)) if self.halted => {
+                return Ok(TickResult {
+                    halted: true,
+                    ..TickResult::default()
+                })
             }
-            Instruction::InsFX07(x) => {
-                self.v_reg[x as usize] = self.delay_timer;
+            result => result?,
+        };
+        Ok(TickResult {
+            drew,
+            beep: self.sound_timer > 0,
+            awaiting_key: matches!(instruction, Instruction::InsFX0A(_)) && self.pc == pc,
+            awaiting_vblank: matches!(instruction, Instruction::InsDXYN(..)) && self.pc == pc,
+            halted: self.halted,
+            self_modified: self.self_modified,
+            runaway,
+        })
+    }
+
+    // 无限迭代器, 每次 next 执行一次 tick, 配合 take/take_while 使用
+    pub fn ticks(&mut self) -> impl Iterator<Item = Result<TickResult, EmuError>> + '_ {
+        core::iter::from_fn(move || Some(self.tick()))
+    }
+
+    // 执行一条指令并返回执行后的状态, 暂停时也可单步; 与 tick 一样, 停机后不再执行
+    pub fn step(&mut self) -> Result<CpuState, EmuError> {
+        let executed = if self.halted {
+            None
+        } else {
+            Some(self.run_instruction()?)
+        };
+        Ok(CpuState {
+            pc: self.pc,
+            i: self.i_reg,
+            v: self.v_reg,
+            sp: self.stack_pointer,
+            dt: self.delay_timer,
+            st: self.sound_timer,
+            executed,
+        })
+    }
+
+    fn run_instruction(&mut self) -> Result<Instruction, EmuError> {
+        if self.pc as usize + 1 >= MEMORY_SIZE {
+            return Err(EmuError::PcOutOfBounds(self.pc));
+        }
+        // 执行失败的指令没有改变状态, 执行成功后才写入撤销记录
+        let entry = (self.journal_depth > 0).then(|| self.journal_entry());
+        if self.trace_depth > 0 {
+            while self.trace.len() >= self.trace_depth {
+                self.trace.pop_front();
             }
-            Instruction::InsFX0A(x) => {
-                let mut pressed = false;
-                for (key_code, &key_pressed) in self.keypad.iter().enumerate() {
-                    if key_pressed {
-                        pressed = true;
-                        self.wait_for_key_release = Some(key_code);
-                        self.v_reg[x as usize] = key_code as u8;
-                        break;
-                    }
-                }
-                if !pressed {
+            self.trace.push_back((self.pc, self.current_opcode()));
+        }
+        let addr = self.pc;
+        let opcode = self.fetch_opcode();
+        let instruction = self.decode(opcode);
+        #[cfg(feature = "logging")]
+        match &instruction {
+            Ok(ins) => log::trace!("{:04x}: {:04x} {:?}", self.pc - 2, opcode, ins),
+            Err(err) => log::warn!("{:04x}: {}", self.pc - 2, err),
+        }
+        let instruction = match instruction {
+            Ok(ins) => ins,
+            Err(err) => {
+                if self.halt_on_unknown_opcode {
                     self.pc -= 2;
+                    self.halt(HaltReason::UnknownOpcode(opcode));
                 }
+                return Err(err.into());
             }
-            Instruction::InsFX15(x) => {
-                self.delay_timer = self.v_reg[x as usize];
-            }
-            Instruction::InsFX18(x) => {
-                self.sound_timer = self.v_reg[x as usize];
-            }
-            Instruction::InsFX1E(x) => {
-                self.i_reg = self.i_reg.wrapping_add(self.v_reg[x as usize] as u16);
-            }
-            Instruction::InsFX29(x) => {
-                self.i_reg = self.v_reg[x as usize] as u16 * 5;
-            }
-            Instruction::InsFX33(x) => {
-                let hundreds = self.v_reg[x as usize] / 100;
-                let tens = (self.v_reg[x as usize] / 10) % 10;
-                let units = self.v_reg[x as usize] % 10;
-                self.memory[self.i_reg as usize] = hundreds;
-                self.memory[self.i_reg as usize + 1] = tens;
-                self.memory[self.i_reg as usize + 2] = units;
-            }
-            Instruction::InsFX55(x) => {
-                for i in 0..=x {
-                    self.memory[(self.i_reg + i as u16) as usize] = self.v_reg[i as usize]
-                }
-                self.i_reg += x as u16 + 1;
+        };
+        if let Err(err) = self.execute_instruction(&instruction) {
+            if matches!(err, EmuError::StackOverflow) {
+                self.halt(HaltReason::StackOverflow);
             }
-            Instruction::InsFX65(x) => {
-                for i in 0..=x {
-                    self.v_reg[i as usize] = self.memory[(self.i_reg + i as u16) as usize];
-                }
-                self.i_reg += x as u16 + 1;
+            return Err(err);
+        }
+        // 跳转到自身是常见的停机写法; 只有取指后才能确定指令所在的地址
+        if matches!(instruction, Instruction::Ins1NNN(nnn) if nnn == addr) {
+            self.halt(HaltReason::InfiniteLoop);
+        }
+        if let Some(entry) = entry {
+            while self.journal.len() >= self.journal_depth {
+                self.journal.pop_front();
             }
+            self.journal.push_back(entry);
+        }
+        self.apply_frozen_registers();
+        self.cycle_count += 1;
+        if self.track_cycles {
+            self.cycles_elapsed += instruction::cost(&instruction) as u64;
         }
+        if self.profiling {
+            self.opcode_counts[instruction.id()] += 1;
+        }
+        Ok(instruction)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::instruction::Instruction;
-    use crate::rng::LinearCongruentialGenerator;
+    fn journal_entry(&self) -> JournalEntry {
+        // 只有 FX33/FX55 会写内存
+        let (addr, len) = match self.peek_instruction() {
+            Ok(Instruction::InsFX33(_)) => (self.i_reg, 3),
+            Ok(Instruction::InsFX55(x)) => (self.i_reg, x as u16 + 1),
+            _ => (self.i_reg, 0),
+        };
+        let end = (addr as usize + len as usize).min(MEMORY_SIZE);
+        JournalEntry {
+            pc: self.pc,
+            i_reg: self.i_reg,
+            v_reg: self.v_reg,
+            stack_pointer: self.stack_pointer,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            halted: self.halted,
+            halt_reason: self.halt_reason,
+            selected_planes: self.selected_planes,
+            audio_pattern: self.audio_pattern,
+            audio_pitch: self.audio_pitch,
+            stack_slot: self
+                .stack
+                .get(self.stack_pointer as usize)
+                .map(|&old| (self.stack_pointer as usize, old)),
+            memory: (addr as usize..end)
+                .map(|a| (a as u16, self.memory[a]))
+                .collect(),
+        }
+    }
 
-    fn create_chip8() -> Chip8<LinearCongruentialGenerator> {
-        let mut c8 = Chip8::new(LinearCongruentialGenerator::default());
-        c8.load_fontset(); // 确保加载字体
-        c8
+    // 撤销最近一条指令对寄存器和内存的修改, 没有记录时返回 false
+    // 不恢复屏幕, 按键和 FX0A 的按键快照, 随机数生成器的位置, cycle_count 以及各项统计
+    pub fn undo_last_tick(&mut self) -> bool {
+        let Some(entry) = self.journal.pop_back() else {
+            return false;
+        };
+        for &(addr, old) in entry.memory.iter().rev() {
+            self.memory[addr as usize] = old;
+        }
+        self.pc = entry.pc;
+        self.i_reg = entry.i_reg;
+        self.v_reg = entry.v_reg;
+        self.stack_pointer = entry.stack_pointer;
+        // stack 是公开字段, 期间可能被替换
+        if let Some((slot, old)) = entry
+            .stack_slot
+            .and_then(|(idx, old)| Some((self.stack.get_mut(idx)?, old)))
+        {
+            *slot = old;
+        }
+        self.delay_timer = entry.delay_timer;
+        self.sound_timer = entry.sound_timer;
+        self.halted = entry.halted;
+        self.halt_reason = entry.halt_reason;
+        self.selected_planes = entry.selected_planes;
+        self.audio_pattern = entry.audio_pattern;
+        self.audio_pitch = entry.audio_pitch;
+        true
     }
 
-    #[test]
-    fn test_memory_load_fontset() {
-        let c8 = create_chip8();
-        // 检查前5个字符（数字0）
-        assert_eq!(&c8.memory[0..5], &[0xF0, 0x90, 0x90, 0x90, 0xF0]);
+    // 最近执行的 (pc, opcode), 从旧到新
+    pub fn recent_trace(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.trace.iter().copied()
     }
 
-    #[test]
-    fn test_rom_loading() {
-        let mut c8 = create_chip8();
-        let rom: [u8; 3] = [0x12, 0x34, 0x56];
-        c8.load_rom(&rom);
-        assert_eq!(&c8.memory[ROM_START..ROM_START + 3], &[0x12, 0x34, 0x56]);
+    pub fn registers(&self) -> [u8; NUM_REGISTERS] {
+        self.v_reg
     }
 
-    #[test]
-    fn test_opcode_execution() {
-        let mut c8 = create_chip8();
-        // 测试 6XNN (LD Vx, NN)
-        c8.memory[0x200] = 0x6A; // Vx = A
-        c8.memory[0x201] = 0xFF; // NN = FF
+    pub fn set_registers(&mut self, regs: [u8; NUM_REGISTERS]) {
+        self.v_reg = regs;
+    }
+
+    // 当前有效的返回地址, 最外层调用在前
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack[..(self.stack_pointer as usize).min(self.stack.len())]
+    }
+
+    // 锁定 Vx, 每条指令执行后都会恢复为 value, reset 不会解除锁定
+    pub fn freeze_register(&mut self, x: usize, value: u8) {
+        if x >= NUM_REGISTERS {
+            return;
+        }
+        self.frozen_mask |= 1 << x;
+        self.frozen_values[x] = value;
+        self.v_reg[x] = value;
+    }
+
+    pub fn unfreeze_register(&mut self, x: usize) {
+        if x < NUM_REGISTERS {
+            self.frozen_mask &= !(1 << x);
+        }
+    }
+
+    fn apply_frozen_registers(&mut self) {
+        for x in 0..NUM_REGISTERS {
+            if self.frozen_mask & (1 << x) != 0 {
+                self.v_reg[x] = self.frozen_values[x];
+            }
+        }
+    }
+
+    // (delay, sound)
+    pub fn timers(&self) -> (u8, u8) {
+        (self.delay_timer, self.sound_timer)
+    }
+
+    pub fn tick_timer(&mut self) {
+        self.tick_timers_by(1);
+    }
+
+    // 单独递减某一个计时器, 不影响另一个, 也不视为新的一帧
+    pub fn tick_delay_timer(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+    }
+
+    pub fn tick_sound_timer(&mut self) {
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+    }
+
+    // 前端低于 60Hz 时一次补上多个计时周期
+    pub fn tick_timers_by(&mut self, n: u8) {
+        // 不足一次 60Hz 更新时不算新的一帧, 否则高频轮询会绕过 display_wait 和 runaway 检测
+        if n == 0 || (self.paused && self.pause_timers) {
+            return;
+        }
+        self.delay_timer = self.delay_timer.saturating_sub(n);
+        self.sound_timer = self.sound_timer.saturating_sub(n);
+        // 计时器更新视为新的一帧开始
+        self.frame_drawn = false;
+        self.since_progress = 0;
+    }
+
+    // 按实际经过的时间以 60Hz 递减计时器, 舍入误差会累计到下一次
+    pub fn update_timers(&mut self, source: &mut impl TimeSource) {
+        // 异常的时间源不能导致溢出
+        let elapsed = source
+            .elapsed_ms()
+            .saturating_mul(TIMER_HZ)
+            .min(i64::MAX as u64 / 2);
+        self.timer_error = self.timer_error.saturating_add(elapsed as i64);
+        let ticks = self.timer_error.saturating_add(500).div_euclid(1000);
+        self.timer_error -= ticks * 1000;
+        self.tick_timers_by(ticks.clamp(0, u8::MAX as i64) as u8);
+    }
+
+    pub fn set_rng(&mut self, rng: R) {
+        self.rng = rng;
+    }
+
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    pub fn rng_log(&self) -> &[u16] {
+        &self.rng_log
+    }
+
+    // 按顺序回放记录的随机数, 用完后回到 rng
+    pub fn replay_rng(&mut self, values: &[u16]) {
+        self.rng_replay = values.iter().copied().collect();
+    }
+
+    fn next_random(&mut self) -> u16 {
+        let value = match self.rng_replay.pop_front() {
+            Some(value) => value,
+            None => self.rng.next().unwrap_or_default(),
+        };
+        if self.record_rng {
+            self.rng_log.push(value);
+        }
+        value
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // halt_on_unknown_opcode 开启时导致停机的操作码, 其地址即当前 pc
+    pub fn unknown_opcode(&self) -> Option<u16> {
+        match self.halt_reason() {
+            Some(HaltReason::UnknownOpcode(opcode)) => Some(opcode),
+            _ => None,
+        }
+    }
+
+    // 停机原因, 直接修改 halted 字段造成的停机没有原因
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason.filter(|_| self.halted)
+    }
+
+    fn halt(&mut self, reason: HaltReason) {
+        self.halted = true;
+        self.halt_reason = Some(reason);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // 超出 0-F 的键被忽略
+    pub fn keypress(&mut self, idx: usize, pressed: bool) {
+        if let Some(key) = self.keypad.get_mut(idx) {
+            *key = pressed;
+        }
+    }
+
+    // 经 key_map 转换后再按下/松开
+    pub fn keypress_mapped(&mut self, physical: usize, pressed: bool) {
+        if let Some(&key) = self.key_map.get(physical) {
+            self.keypress(key as usize, pressed);
+        }
+    }
+
+    // '0'-'9', 'a'-'f' (不区分大小写) 对应 16 个键, 其他字符返回 false
+    pub fn keypress_char(&mut self, c: char, pressed: bool) -> bool {
+        match c.to_digit(16) {
+            Some(key) => {
+                self.keypress(key as usize, pressed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 与 keypress 相同, 但只在按键从松开变为按下时返回 true
+    pub fn keypress_edge(&mut self, idx: usize, pressed: bool) -> bool {
+        let Some(key) = self.keypad.get_mut(idx) else {
+            return false;
+        };
+        let rising = pressed && !*key;
+        *key = pressed;
+        rising
+    }
+
+    // 按 target_hz 计算的模拟时间与实际时间之差(毫秒), 正数表示模拟领先
+    #[cfg(feature = "std")]
+    pub fn timing_drift_ms(&self, target_hz: u32, real_elapsed: std::time::Duration) -> f64 {
+        let emulated_ms = self.cycle_count as f64 * 1000.0 / target_hz as f64;
+        emulated_ms - real_elapsed.as_secs_f64() * 1000.0
+    }
+
+    // 反复用 DXYN 的绘制路径画字体 "0", 返回耗时, 用于对比绘制路径优化前后的性能
+    // 绕过 display_wait, 结束后恢复所有受影响的状态(寄存器, 屏幕, 脏矩形, 统计等)
+    #[cfg(feature = "std")]
+    pub fn bench_draw(&mut self, iterations: usize) -> std::time::Duration {
+        let saved_i = self.i_reg;
+        let saved_v = self.v_reg;
+        let saved_screens = (self.screen.clone(), self.plane1.clone());
+        let saved_flags = (self.draw_flag, self.last_draw_font, self.redundant_draws);
+        let saved_collisions = core::mem::take(&mut self.last_collision_pixels);
+        self.i_reg = FONTSET_START as u16;
+        self.v_reg[0] = 3;
+        self.v_reg[1] = 5;
+        let start = std::time::Instant::now();
+        for _ in 0..iterations {
+            // 字体在内存内, 不会失败
+            let _ = self.draw_sprite(0, 1, 5);
+        }
+        let elapsed = start.elapsed();
+        self.i_reg = saved_i;
+        self.v_reg = saved_v;
+        (self.screen, self.plane1) = saved_screens;
+        (self.draw_flag, self.last_draw_font, self.redundant_draws) = saved_flags;
+        self.last_collision_pixels = saved_collisions;
+        elapsed
+    }
+
+    // 应用按键脚本中到期的事件, 通常传入当前的 cycle_count
+    pub fn apply_key_events_at(&mut self, cycle: u64) {
+        for event in self.key_script.take_due(cycle) {
+            if let Some(key) = self.keypad.get_mut(event.key) {
+                *key = event.pressed;
+            }
+        }
+    }
+
+    // |   |
+    // | h | 0xA2 -> 左移8位 0xA200
+    // |_l_| 0xF0 -> 按位或  0xA2F0
+    // Chip8 大端格式
+    fn fetch_opcode(&mut self) -> u16 {
+        let opcode = self.opcode_at(self.pc);
+
+        // Chip8 操作码都是 2 字节
+        self.pc += 2;
+
+        opcode
+    }
+
+    // 超出内存的字节视为 0
+    fn opcode_at(&self, addr: u16) -> u16 {
+        let byte = |a: usize| self.memory.get(a).copied().unwrap_or(0) as u16;
+        (byte(addr as usize) << 8) | byte(addr as usize + 1)
+    }
+
+    // pc 处的原始操作码, 不解码也不移动 pc
+    pub fn current_opcode(&self) -> u16 {
+        self.opcode_at(self.pc)
+    }
+
+    // 解码 pc 处的指令, 不修改任何状态
+    pub fn peek_instruction(&self) -> Result<Instruction, InstructionError> {
+        self.instruction_at(self.pc)
+    }
+
+    // 解码任意地址处的两个字节, 超出内存的部分按 0 处理
+    pub fn instruction_at(&self, addr: u16) -> Result<Instruction, InstructionError> {
+        self.decode(self.opcode_at(addr))
+    }
+
+    // 按 strict_decode 解码
+    fn decode(&self, opcode: u16) -> Result<Instruction, InstructionError> {
+        match self.decode_checked(opcode) {
+            Err(_) if !self.strict_decode => Ok(Instruction::lenient_fallback(opcode)),
+            result => result,
+        }
+    }
+
+    // 不考虑 strict_decode; 未开启 xo_chip 时 XO-CHIP 扩展指令视为未知操作码
+    fn decode_checked(&self, opcode: u16) -> Result<Instruction, InstructionError> {
+        match Instruction::try_from(opcode)? {
+            ins if ins.is_xo_chip() && !self.xo_chip => {
+                Err(InstructionError::UnknownOpcode(opcode))
+            }
+            ins => Ok(ins),
+        }
+    }
+
+    // 当前分辨率下的屏幕尺寸, 前端据此分配帧缓冲
+    pub const fn screen_width(&self) -> usize {
+        W
+    }
+
+    pub const fn screen_height(&self) -> usize {
+        H
+    }
+
+    // XO-CHIP 音频: 128 个 1 位样本, 高位在前
+    pub fn audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.audio_pattern
+    }
+
+    // 播放速率为 4000 * 2^((pitch - 64) / 48) Hz
+    pub fn audio_pitch(&self) -> u8 {
+        self.audio_pitch
+    }
+
+    // 声音计时器为 0 时静音
+    pub fn current_tone(&self) -> Tone {
+        if self.sound_timer == 0 {
+            Tone::Silent
+        } else if self.xo_chip {
+            Tone::Pattern {
+                bytes: self.audio_pattern,
+                pitch: self.audio_pitch,
+            }
+        } else {
+            Tone::Square { hz: self.tone_hz }
+        }
+    }
+
+    // 每帧调用一次: 点亮的像素亮度置为 255, 熄灭的像素按 amount 衰减
+    pub fn age_display(&mut self, amount: u8) {
+        if self.fade.is_empty() {
+            self.fade = vec![0; W * H];
+        }
+        for (level, on) in self.fade.iter_mut().zip(self.screen.pixels()) {
+            *level = if on {
+                u8::MAX
+            } else {
+                level.saturating_sub(amount)
+            };
+        }
+    }
+
+    // 每个像素的亮度 (0-255), 索引与 get_display 相同, 用于模拟 CRT 余辉
+    pub fn fade_buffer(&self) -> Vec<u8> {
+        let display = self.screen.pixels();
+        if self.fade.is_empty() {
+            return display.map(|on| on as u8 * u8::MAX).collect();
+        }
+        self.fade
+            .iter()
+            .zip(display)
+            .map(|(&level, on)| if on { u8::MAX } else { level })
+            .collect()
+    }
+
+    // 通常意味着程序正在显示数字
+    pub fn last_draw_was_font(&self) -> bool {
+        self.last_draw_font
+    }
+
+    // 屏幕按位存储, 这里展开为每像素一个 bool 的副本, 索引为 x + y * W
+    // 原先返回 &[bool]; 需要逐帧读取时可改用 display_row 避免分配
+    pub fn get_display(&self) -> Vec<bool> {
+        self.screen.pixels().collect()
+    }
+
+    // 打包后的一行, 第 x 列对应第 127 - x 位
+    pub fn display_row(&self, y: usize) -> u128 {
+        self.screen.row(y)
+    }
+
+    // 各指令的执行次数, 只包含执行过的指令
+    pub fn opcode_histogram(&self) -> BTreeMap<&'static str, u64> {
+        INSTRUCTION_NAMES
+            .iter()
+            .zip(self.opcode_counts.iter())
+            .filter(|&(_, &count)| count > 0)
+            .map(|(&name, &count)| (name, count))
+            .collect()
+    }
+
+    // 与上一帧比较, 返回变化像素的 (索引, 新值); prev 长度不足的部分视为熄灭
+    pub fn display_diff(&self, prev: &[bool]) -> Vec<(u16, bool)> {
+        self.screen
+            .pixels()
+            .enumerate()
+            .filter(|&(idx, pixel)| prev.get(idx).copied().unwrap_or(false) != pixel)
+            .map(|(idx, pixel)| (idx as u16, pixel))
+            .collect()
+    }
+
+    // 终端调试用, 点亮的像素为 '█', 每行以换行结尾
+    pub fn render_ascii(&self) -> String {
+        let mut out = String::with_capacity((W * '█'.len_utf8() + 1) * H);
+        for row in self.screen.rows() {
+            out.extend(row.map(|on| if on { '█' } else { ' ' }));
+            out.push('\n');
+        }
+        out
+    }
+
+    // 放大后的 RGBA 缓冲区, 可直接上传为纹理; scale 过大导致尺寸溢出时返回空缓冲区
+    pub fn render_rgba(&self, scale: usize, on: [u8; 4], off: [u8; 4]) -> Vec<u8> {
+        let Some((width, len)) = W.checked_mul(scale).and_then(|width| {
+            let len = width.checked_mul(H)?.checked_mul(scale)?.checked_mul(4)?;
+            Some((width, len))
+        }) else {
+            return Vec::new();
+        };
+        let mut buf = Vec::with_capacity(len);
+        for row in self.screen.rows() {
+            let start = buf.len();
+            for pixel in row {
+                let color = if pixel { on } else { off };
+                for _ in 0..scale {
+                    buf.extend_from_slice(&color);
+                }
+            }
+            // 纵向放大直接复制整行
+            for _ in 1..scale {
+                buf.extend_from_within(start..start + width * 4);
+            }
+        }
+        buf
+    }
+
+    // 没有点亮任何新像素的 DXYN 次数, 仅在 profiling 开启时统计
+    pub fn redundant_draws(&self) -> u64 {
+        self.redundant_draws
+    }
+
+    // 自上次调用以来屏幕改变的区域 (x0, y0, x1, y1)
+    pub fn take_dirty_rect(&mut self) -> Option<(usize, usize, usize, usize)> {
+        self.screen.take_dirty_rect()
+    }
+
+    // 清空所有平面
+    pub fn clear_display(&mut self) {
+        self.screen.clear();
+        self.plane1.clear();
+        self.draw_flag = true;
+    }
+
+    pub fn clear_keys(&mut self) {
+        self.keypad = [false; KEYPAD_SIZE];
+    }
+
+    // 部分硬件和测试 ROM 依赖未初始化内存的内容, 在下次 reset 时生效
+    pub fn set_memory_fill(&mut self, byte: u8) {
+        self.memory_fill = byte;
+    }
+
+    pub fn reset(&mut self) {
+        self.pc = PROGRAM_START;
+        self.memory = [self.memory_fill; MEMORY_SIZE];
+        self.rom_len = 0;
+        self.screen.clear();
+        self.plane1.clear();
+        self.selected_planes = 0b01;
+        self.v_reg = [0; NUM_REGISTERS];
+        self.i_reg = 0;
+        self.stack_pointer = 0;
+        self.stack.fill(0);
+        self.keypad = [false; KEYPAD_SIZE];
+        self.key_wait_snapshot = None;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.draw_flag = false;
+        self.last_draw_font = false;
+        self.halted = false;
+        self.halt_reason = None;
+        self.cycle_count = 0;
+        self.since_progress = 0;
+        self.cycles_elapsed = 0;
+        self.opcode_counts = [0; INSTRUCTION_COUNT];
+        self.redundant_draws = 0;
+        self.trace.clear();
+        self.journal.clear();
+        self.fade.clear();
+        self.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+        self.audio_pitch = DEFAULT_AUDIO_PITCH;
+        self.rng_log.clear();
+        self.rng_replay.clear();
+        self.reload_fontset();
+    }
+
+    // 在 reset 的基础上清除 reset 保留的运行时状态, 并把影响执行的设置恢复为 new() 的默认值,
+    // 保证回放结果不依赖之前的使用; 只影响统计和调试输出的设置保持不变
+    pub(crate) fn reset_for_replay(&mut self) {
+        self.memory_fill = 0;
+        self.stack = vec![0; STACK_SIZE];
+        self.reset();
+        self.draw_mode = DrawMode::default();
+        self.sys_hook = None;
+        self.record_rng = false;
+        self.allow_font_write = false;
+        self.protect_interpreter_region = false;
+        self.warn_self_modify = false;
+        self.runaway_threshold = None;
+        self.halt_on_unknown_opcode = false;
+        self.pause_timers = false;
+        self.paused = false;
+        self.turbo = false;
+        self.frozen_mask = 0;
+        self.wait_for_key_release = None;
+        self.frame_drawn = false;
+        self.timer_error = 0;
+    }
+
+    // 存档格式: 魔数, 版本, 内存, 寄存器, 调用栈, 按键, 屏幕(按位打包), 停机状态,
+    // ROM 长度, XO-CHIP 平面 1, 平面选择, 音频样本与音高, 停机原因
+    // 多字节字段统一使用大端序, 与操作码一致, 不依赖主机字节序
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(MEMORY_SIZE + W * H / 4 + 128);
+        buf.extend_from_slice(STATE_MAGIC);
+        buf.push(STATE_VERSION);
+        buf.extend_from_slice(&self.memory);
+        buf.extend_from_slice(&self.pc.to_be_bytes());
+        buf.extend_from_slice(&self.i_reg.to_be_bytes());
+        buf.extend_from_slice(&self.v_reg);
+        buf.push(self.delay_timer);
+        buf.push(self.sound_timer);
+        buf.extend_from_slice(&(self.stack.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&self.stack_pointer.to_be_bytes());
+        for entry in &self.stack {
+            buf.extend_from_slice(&entry.to_be_bytes());
+        }
+        buf.extend(self.keypad.iter().map(|&k| k as u8));
+        buf.extend_from_slice(&(W as u16).to_be_bytes());
+        buf.extend_from_slice(&(H as u16).to_be_bytes());
+        pack_screen(&self.screen, &mut buf);
+        buf.push(self.halted as u8);
+        buf.extend_from_slice(&((self.rom_end() - ROM_START) as u16).to_be_bytes());
+        pack_screen(&self.plane1, &mut buf);
+        buf.push(self.selected_planes);
+        buf.extend_from_slice(&self.audio_pattern);
+        buf.push(self.audio_pitch);
+        let (tag, opcode) = encode_halt_reason(self.halt_reason);
+        buf.push(tag);
+        buf.extend_from_slice(&opcode.to_be_bytes());
+        buf
+    }
+
+    // 出错时不修改任何状态
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader { buf: data, pos: 0 };
+        if r.bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = r.u8()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        let memory = r.bytes(MEMORY_SIZE)?;
+        let pc = r.u16()?;
+        let i_reg = r.u16()?;
+        let v_reg = r.bytes(NUM_REGISTERS)?;
+        let delay_timer = r.u8()?;
+        let sound_timer = r.u8()?;
+        let depth = r.u16()? as usize;
+        let stack_pointer = r.u16()?;
+        if stack_pointer as usize > depth {
+            return Err(StateError::InvalidStackPointer(stack_pointer));
+        }
+        let stack = (0..depth).map(|_| r.u16()).collect::<Result<Vec<_>, _>>()?;
+        let keypad = r.bytes(KEYPAD_SIZE)?;
+        let (width, height) = (r.u16()? as usize, r.u16()? as usize);
+        if (width, height) != (W, H) {
+            return Err(StateError::ResolutionMismatch);
+        }
+        let pixels = r.bytes((W * H).div_ceil(8))?;
+        let halted = r.u8()? != 0;
+        let rom_len = r.u16()? as usize;
+        let plane1 = r.bytes((W * H).div_ceil(8))?;
+        let selected_planes = r.u8()?;
+        let audio_pattern = r.bytes(AUDIO_PATTERN_SIZE)?;
+        let audio_pitch = r.u8()?;
+        let (tag, opcode) = (r.u8()?, r.u16()?);
+        let halt_reason = decode_halt_reason(tag, opcode)?;
+
+        self.memory.copy_from_slice(memory);
+        self.pc = pc;
+        self.i_reg = i_reg;
+        self.v_reg.copy_from_slice(v_reg);
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.stack = stack;
+        self.stack_pointer = stack_pointer;
+        for (key, &byte) in self.keypad.iter_mut().zip(keypad) {
+            *key = byte != 0;
+        }
+        unpack_screen(&mut self.screen, pixels);
+        unpack_screen(&mut self.plane1, plane1);
+        self.halted = halted;
+        self.halt_reason = halt_reason;
+        self.rom_len = rom_len;
+        self.selected_planes = selected_planes;
+        self.audio_pattern.copy_from_slice(audio_pattern);
+        self.audio_pitch = audio_pitch;
+        // FX0A 的按键快照属于被替换的状态
+        self.key_wait_snapshot = None;
+        self.draw_flag = true;
+        // 旧的撤销记录属于被替换的状态
+        self.journal.clear();
+        Ok(())
+    }
+
+    // 8XY5/8XY7 的 VF, 默认 1 表示没有借位
+    fn borrow_flag(&self, borrow: bool) -> u8 {
+        (borrow == self.quirks.subtract_borrow_inverted) as u8
+    }
+
+    // DXYN 的绘制部分, 不包括 display_wait 的等待
+    fn draw_sprite(&mut self, x: u8, y: u8, n: u8) -> Result<(), EmuError> {
+        // I 设置错误时精灵数据可能越过内存末尾
+        // XO-CHIP 每个选中的平面依次使用 N 字节的精灵数据
+        let planes = if self.xo_chip {
+            self.selected_planes & 0b11
+        } else {
+            0b01
+        };
+        self.check_range(self.i_reg, n as u16 * planes.count_ones() as u16)?;
+        self.last_draw_font = self.i_reg < PROGRAM_START;
+        let vx = self.v_reg[x as usize] as usize % W;
+        let vy = self.v_reg[y as usize] as usize % H;
+        if self.quirks.vf_reset_on_draw {
+            self.v_reg[0xF] = 0;
+        }
+        if self.track_collisions {
+            self.last_collision_pixels.clear();
+        }
+        let mut drawn = false;
+        let mut collision = false;
+        let mut rows_drawn = 0;
+        let mut lit = false; // 是否有像素由灭变亮
+        let mut addr = self.i_reg;
+        let mut collided_rows = 0u8;
+        // 越过底边的行数只取决于 VY 和 N, 每个精灵只计一次
+        let clipped_rows = if self.quirks.wrap_y {
+            0
+        } else {
+            (vy + n as usize).saturating_sub(H) as u8
+        };
+        for plane in (0..2).filter(|p| planes & (1 << p) != 0) {
+            for row in 0..n {
+                let mut screen_y = vy + row as usize;
+                if screen_y >= H {
+                    if !self.quirks.wrap_y {
+                        break;
+                    }
+                    screen_y %= H;
+                }
+                let sprite_row = self.memory[(addr + row as u16) as usize];
+                let (row_drawn, row_collision, row_lit) =
+                    self.draw_sprite_byte(plane, vx, screen_y, sprite_row);
+                drawn |= row_drawn;
+                collision |= row_collision;
+                collided_rows += row_collision as u8;
+                lit |= row_lit;
+                rows_drawn += 1;
+            }
+            addr += n as u16;
+        }
+        // 整个精灵被裁剪(或 N=0)时屏幕没有变化, 无需重绘
+        if rows_drawn > 0 {
+            self.draw_flag = true;
+        }
+        if self.quirks.schip_collision_rows {
+            self.v_reg[0xF] = collided_rows + clipped_rows;
+        } else if self.quirks.vf_reset_on_draw || drawn {
+            self.v_reg[0xF] = collision as u8;
+        }
+        // 没有点亮任何新像素: 空操作或把精灵整个擦掉
+        if self.profiling && !lit {
+            self.redundant_draws += 1;
+        }
+        Ok(())
+    }
+
+    // 在指定平面上从 (x, y) 开始绘制精灵的一行, 返回 (有点亮的精灵像素, 发生碰撞, 有像素由灭变亮)
+    fn draw_sprite_byte(
+        &mut self,
+        plane: usize,
+        x: usize,
+        y: usize,
+        byte: u8,
+    ) -> (bool, bool, bool) {
+        let screen = if plane == 0 {
+            &mut self.screen
+        } else {
+            &mut self.plane1
+        };
+        let mut x = x;
+        if x >= W {
+            if !self.quirks.wrap_x {
+                return (false, false, false);
+            }
+            x %= W;
+        }
+        // 整行处理: 精灵字节移到第 x 列, 超出右边缘的部分按 wrap_x 回绕到左侧或丢弃
+        let row = (byte as u128) << 120;
+        let (bits, span) = if x.is_multiple_of(8) && x + 8 <= W {
+            // 按字节对齐且没有被裁剪, 不需要掩码和回绕
+            (row >> x, 0xFFu128 << 120 >> x)
+        } else {
+            let mut bits = (row >> x) & Screen::<W, H>::ROW_MASK;
+            let mut span = (0xFFu128 << 120 >> x) & Screen::<W, H>::ROW_MASK;
+            if x + 8 > W && self.quirks.wrap_x {
+                bits |= row << (W - x);
+                span |= 0xFFu128 << 120 << (W - x);
+            }
+            (bits, span)
+        };
+        let old = screen.row(y);
+        let drawn = bits != 0;
+        let lit = bits & !old != 0;
+        let collision = match self.draw_mode {
+            // 碰撞检测 VF碰撞检测标志位
+            DrawMode::Xor => {
+                let collided = screen.xor_row(y, bits);
+                if self.track_collisions {
+                    // 按精灵的列顺序记录
+                    for col in 0..8 {
+                        let screen_x = (x + col) % W;
+                        if (x + col < W || self.quirks.wrap_x)
+                            && collided & (1 << (127 - screen_x)) != 0
+                        {
+                            self.last_collision_pixels.push((screen_x as u8, y as u8));
+                        }
+                    }
+                }
+                collided != 0
+            }
+            DrawMode::Overwrite => {
+                screen.write_row(y, span, bits);
+                false
+            }
+        };
+        (drawn, collision, lit)
+    }
+
+    // 绘制宽度超过 8 像素的图像, 每行 width_bytes 个字节, 按 DXYN 的规则裁剪或回绕
+    // 不修改 VF, 返回是否发生碰撞
+    pub fn draw_image(&mut self, x: u8, y: u8, bytes: &[u8], width_bytes: usize) -> bool {
+        if width_bytes == 0 {
+            return false;
+        }
+        let (x0, y0) = (x as usize % W, y as usize % H);
+        if self.track_collisions {
+            self.last_collision_pixels.clear();
+        }
+        let mut collision = false;
+        for (row, line) in bytes.chunks(width_bytes).enumerate() {
+            let mut screen_y = y0 + row;
+            if screen_y >= H {
+                if !self.quirks.wrap_y {
+                    break;
+                }
+                screen_y %= H;
+            }
+            for (col, &byte) in line.iter().enumerate() {
+                collision |= self.draw_sprite_byte(0, x0 + col * 8, screen_y, byte).1;
+            }
+            self.draw_flag = true;
+        }
+        collision
+    }
+
+    // 检查指令即将访问的内存区间 [addr, addr + len) 是否越界
+    fn check_range(&self, addr: u16, len: u16) -> Result<(), EmuError> {
+        if addr as usize + len as usize > MEMORY_SIZE {
+            return Err(EmuError::MemoryOutOfBounds(addr));
+        }
+        Ok(())
+    }
+
+    // 检查指令即将写入的内存区间 [addr, addr + len)
+    fn check_write(&mut self, addr: u16, len: u16) -> Result<(), EmuError> {
+        self.check_range(addr, len)?;
+        if self.protect_interpreter_region && addr < PROGRAM_START {
+            return Err(EmuError::ProtectedWrite(addr));
+        }
+        let rom_end = self.rom_end();
+        let (start, end) = (addr as usize, addr as usize + len as usize);
+        if self.warn_self_modify && start < rom_end && end > ROM_START {
+            #[cfg(feature = "logging")]
+            log::warn!("{:04x}: self-modifying write at {:04x}", self.pc - 2, addr);
+            self.self_modified = true;
+        }
+        Ok(())
+    }
+
+    // 直接执行给定的操作码而不从内存取指, 跳转类指令仍会修改 pc
+    pub fn execute_opcode(&mut self, opcode: u16) -> Result<(), EmuError> {
+        let instruction = self.decode(opcode)?;
+        self.execute_instruction(&instruction)
+    }
+
+    fn execute_instruction(&mut self, instruction: &Instruction) -> Result<(), EmuError> {
+        match *instruction {
+            Instruction::Ins00E0 => {
+                // XO-CHIP 只清除选中的平面
+                if self.xo_chip {
+                    if self.selected_planes & 0b01 != 0 {
+                        self.screen.clear();
+                    }
+                    if self.selected_planes & 0b10 != 0 {
+                        self.plane1.clear();
+                    }
+                    self.draw_flag = true;
+                } else {
+                    self.clear_display();
+                }
+            }
+            Instruction::Ins00EE => {
+                let sp = self
+                    .stack_pointer
+                    .checked_sub(1)
+                    .ok_or(EmuError::StackUnderflow)?;
+                // stack_pointer 被外部改坏时不能直接索引, 出错时不修改任何状态
+                self.pc = *self
+                    .stack
+                    .get(sp as usize)
+                    .ok_or(EmuError::BadReturn(self.stack_pointer))?;
+                self.stack_pointer = sp;
+            }
+            Instruction::Ins00FD => {
+                self.halt(HaltReason::Exit);
+            }
+            Instruction::Ins0NNN(nnn) => {
+                if let Some(hook) = self.sys_hook {
+                    hook(self, nnn);
+                }
+            }
+            Instruction::Ins1NNN(nnn) => {
+                self.pc = nnn;
+            }
+            Instruction::Ins2NNN(nnn) => {
+                // 调用栈可能长于 u16::MAX, 此时 stack_pointer 本身也会溢出
+                if self.stack_pointer as usize >= self.stack.len() || self.stack_pointer == u16::MAX
+                {
+                    return Err(EmuError::StackOverflow);
+                }
+                self.stack[self.stack_pointer as usize] = self.pc;
+                self.stack_pointer += 1;
+                self.pc = nnn;
+            }
+            Instruction::Ins3XNN(x, nn) => {
+                if self.v_reg[x as usize] == nn {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::Ins4XNN(x, nn) => {
+                if self.v_reg[x as usize] != nn {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::Ins5XY0(x, y) => {
+                if self.v_reg[x as usize] == self.v_reg[y as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::Ins6XNN(x, nn) => {
+                self.v_reg[x as usize] = nn;
+            }
+            Instruction::Ins7XNN(x, nn) => {
+                // 只保留低 8 位, 不影响进位标志; 7FNN 直接把结果写入 VF
+                self.v_reg[x as usize] = self.v_reg[x as usize].wrapping_add(nn);
+            }
+            Instruction::Ins8XY0(x, y) => {
+                self.v_reg[x as usize] = self.v_reg[y as usize];
+            }
+            Instruction::Ins8XY1(x, y) => {
+                self.v_reg[x as usize] |= self.v_reg[y as usize];
+                if self.quirks.logic_clears_vf {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+            Instruction::Ins8XY2(x, y) => {
+                self.v_reg[x as usize] &= self.v_reg[y as usize];
+                if self.quirks.logic_clears_vf {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+            Instruction::Ins8XY3(x, y) => {
+                self.v_reg[x as usize] ^= self.v_reg[y as usize];
+                if self.quirks.logic_clears_vf {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+            Instruction::Ins8XY4(x, y) => {
+                let (res, carry) = self.v_reg[x as usize].overflowing_add(self.v_reg[y as usize]);
+                self.v_reg[x as usize] = res;
+                self.v_reg[0xF] = carry as u8;
+            }
+            Instruction::Ins8XY5(x, y) => {
+                let (res, borrow) = self.v_reg[x as usize].overflowing_sub(self.v_reg[y as usize]);
+                self.v_reg[x as usize] = res;
+                self.v_reg[0xF] = self.borrow_flag(borrow);
+            }
+            Instruction::Ins8XY6(x, y) => {
+                if self.quirks.shift_uses_vy {
+                    self.v_reg[x as usize] = self.v_reg[y as usize];
+                }
+                let dropped = self.v_reg[x as usize] & 1;
+                self.v_reg[x as usize] >>= 1;
+                self.v_reg[0xF] = dropped;
+            }
+            Instruction::Ins8XY7(x, y) => {
+                let (res, borrow) = self.v_reg[y as usize].overflowing_sub(self.v_reg[x as usize]);
+                self.v_reg[x as usize] = res;
+                self.v_reg[0xF] = self.borrow_flag(borrow);
+            }
+            Instruction::Ins8XYE(x, y) => {
+                if self.quirks.shift_uses_vy {
+                    self.v_reg[x as usize] = self.v_reg[y as usize];
+                }
+                let dropped = self.v_reg[x as usize] >> 7;
+                self.v_reg[x as usize] <<= 1;
+                self.v_reg[0xF] = dropped;
+            }
+            Instruction::Ins9XY0(x, y) => {
+                if self.v_reg[x as usize] != self.v_reg[y as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::InsANNN(nnn) => {
+                self.i_reg = nnn;
+            }
+            Instruction::InsBNNN(nnn) => {
+                // 地址总线只有 12 位
+                self.pc = (self.v_reg[0] as u16 + nnn) & 0x0FFF;
+            }
+            Instruction::InsCXNN(x, nn) => {
+                let random = self.next_random();
+                self.v_reg[x as usize] = random as u8 & nn;
+            }
+            Instruction::InsDXYN(x, y, n) => {
+                if self.quirks.display_wait && !self.turbo {
+                    if self.frame_drawn {
+                        // 重复执行本指令直到下一帧
+                        self.pc = self.pc.wrapping_sub(2);
+                        return Ok(());
+                    }
+                    self.frame_drawn = true;
+                }
+                self.draw_sprite(x, y, n)?;
+            }
+            // 只取 VX 的低 4 位, 避免越界访问按键数组
+            Instruction::InsEX9E(x) => {
+                if self.keypad[(self.v_reg[x as usize] & 0xF) as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::InsEXA1(x) => {
+                if !self.keypad[(self.v_reg[x as usize] & 0xF) as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Instruction::InsFX07(x) => {
+                self.v_reg[x as usize] = self.delay_timer;
+            }
+            Instruction::InsFX0A(x) => {
+                // 进入等待时已按住的键不算, 只响应新的按下
+                let held = self.key_wait_snapshot.get_or_insert(self.keypad);
+                for (held, &pressed) in held.iter_mut().zip(self.keypad.iter()) {
+                    *held &= pressed;
+                }
+                let fresh = (0..KEYPAD_SIZE).find(|&k| self.keypad[k] && !held[k]);
+                match fresh {
+                    Some(key_code) => {
+                        self.key_wait_snapshot = None;
+                        self.wait_for_key_release = Some(key_code);
+                        self.v_reg[x as usize] = key_code as u8;
+                    }
+                    None => self.pc = self.pc.wrapping_sub(2),
+                }
+            }
+            Instruction::InsFX15(x) => {
+                self.delay_timer = self.v_reg[x as usize];
+            }
+            Instruction::InsFX18(x) => {
+                self.sound_timer = self.v_reg[x as usize];
+            }
+            Instruction::InsFX1E(x) => {
+                let sum = self.i_reg.wrapping_add(self.v_reg[x as usize] as u16);
+                if self.quirks.i_overflow_sets_vf {
+                    self.v_reg[0xF] = (sum > 0xFFF) as u8;
+                }
+                // 地址总线只有 12 位
+                self.i_reg = sum & 0x0FFF;
+            }
+            Instruction::InsFX29(x) => {
+                self.i_reg = self.v_reg[x as usize] as u16 * 5;
+            }
+            Instruction::InsFX33(x) => {
+                self.check_write(self.i_reg, 3)?;
+                let i = self.i_reg as usize;
+                self.memory[i..i + 3].copy_from_slice(&bcd(self.v_reg[x as usize]));
+            }
+            Instruction::InsFX55(x) => {
+                self.check_write(self.i_reg, x as u16 + 1)?;
+                for i in 0..=x {
+                    self.memory[(self.i_reg + i as u16) as usize] = self.v_reg[i as usize]
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i_reg += x as u16 + 1;
+                }
+            }
+            Instruction::InsFX65(x) => {
+                self.check_range(self.i_reg, x as u16 + 1)?;
+                for i in 0..=x {
+                    self.v_reg[i as usize] = self.memory[(self.i_reg + i as u16) as usize];
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i_reg += x as u16 + 1;
+                }
+            }
+            Instruction::InsF002 => {
+                self.check_range(self.i_reg, AUDIO_PATTERN_SIZE as u16)?;
+                let start = self.i_reg as usize;
+                self.audio_pattern
+                    .copy_from_slice(&self.memory[start..start + AUDIO_PATTERN_SIZE]);
+            }
+            Instruction::InsFN01(n) => {
+                self.selected_planes = n & 0b11;
+            }
+            Instruction::InsUnknown(_) => {}
+            Instruction::InsFX3A(x) => {
+                self.audio_pitch = self.v_reg[x as usize];
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> Chip8<LinearCongruentialGenerator, W, H> {
+    // 以 ROM 的 CRC32 作为种子, 同一 ROM 的 CXNN 序列可复现
+    pub fn reseed_from_rom(&mut self) {
+        let crc = self.rom_checksum();
+        self.rng.seed = (crc ^ (crc >> 16)) as u16;
+    }
+}
+
+// 屏幕只输出点亮的像素数量, 避免打印整个缓冲区
+impl<R, const W: usize, const H: usize> core::fmt::Debug for Chip8<R, W, H>
+where
+    R: Iterator<Item = u16>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Chip8 {{ pc={:04x} i={:04x} sp={} dt={} st={}",
+            self.pc, self.i_reg, self.stack_pointer, self.delay_timer, self.sound_timer
+        )?;
+        for (idx, v) in self.v_reg.iter().enumerate() {
+            write!(f, " V{:X}={:02x}", idx, v)?;
+        }
+        let pixels = self.screen.pixels().filter(|&p| p).count();
+        write!(f, " pixels set: {} }}", pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+    use crate::rng::{LinearCongruentialGenerator, MockRng};
+
+    fn create_chip8() -> Chip8<LinearCongruentialGenerator> {
+        let mut c8 = Chip8::new(LinearCongruentialGenerator::default());
+        c8.load_fontset(); // 确保加载字体
+        c8
+    }
+
+    #[test]
+    fn test_debug_format() {
+        let mut c8 = create_chip8();
+        c8.v_reg[0] = 0xAB;
+        c8.screen.set(1, 1, true);
+        let output = alloc::format!("{:?}", c8);
+        assert!(output.contains("pc=0200"));
+        assert!(output.contains("V0=ab"));
+        assert!(output.contains("VF=00"));
+        assert!(output.contains("pixels set: 1"));
+    }
+
+    #[test]
+    fn test_memory_load_fontset() {
+        let c8 = create_chip8();
+        // 检查前5个字符（数字0）
+        assert_eq!(&c8.memory[0..5], &[0xF0, 0x90, 0x90, 0x90, 0xF0]);
+    }
+
+    #[test]
+    fn test_rom_loading() {
+        let mut c8 = create_chip8();
+        let rom: [u8; 3] = [0x12, 0x34, 0x56];
+        c8.load_rom(&rom).unwrap();
+        assert_eq!(&c8.memory[ROM_START..ROM_START + 3], &[0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn test_rom_checksum() {
+        let mut c8 = create_chip8();
+        assert_eq!(c8.rom_checksum(), 0);
+        c8.load_rom(&[0x6A, 0x2A, 0x00, 0xEE]).unwrap();
+        assert_eq!(c8.rom_len, 4);
+        assert_eq!(c8.rom_checksum(), 0x07B7_C432);
+    }
+
+    #[test]
+    fn test_detect_quirks() {
+        let mut c8 = create_chip8();
+        assert_eq!(c8.detect_quirks(), None);
+        c8.load_rom(include_bytes!("../../roms/clzz logo.ch8"))
+            .unwrap();
+        assert_eq!(c8.detect_quirks(), Some(Quirks::default()));
+        c8.reset();
+        c8.load_rom(include_bytes!("../../roms/IBM Logo.ch8"))
+            .unwrap();
+        assert_eq!(c8.detect_quirks(), Some(Quirks::default()));
+
+        // 测试用的 ROM 不在内置表中, 由测试自己建表
+        // 两个 ROM 在错误的 quirks 下显示不同的数字, V0 即显示的数字
+        let shift: &[u8] = include_bytes!("../testdata/shift quirk.ch8");
+        let load_store: &[u8] = include_bytes!("../testdata/load store quirk.ch8");
+        let table = [
+            KnownRom {
+                checksum: checksum::crc32(shift),
+                title: "shift quirk",
+                quirks: Quirks::superchip(),
+            },
+            KnownRom {
+                checksum: checksum::crc32(load_store),
+                title: "load store quirk",
+                quirks: Quirks::cosmac_vip(),
+            },
+        ];
+        let roms = [
+            (shift, Quirks::superchip(), 3),
+            (load_store, Quirks::cosmac_vip(), 0),
+        ];
+        for (rom, expected, digit) in roms {
+            c8.reset();
+            c8.load_rom(rom).unwrap();
+            assert_eq!(c8.detect_quirks(), None);
+            let quirks = c8.detect_quirks_in(&table).unwrap();
+            assert_eq!(quirks, expected);
+            assert_ne!(quirks, Quirks::default());
+            c8.quirks = quirks;
+            while !c8.halted {
+                c8.tick().unwrap();
+            }
+            assert_eq!(c8.v_reg[0], digit);
+        }
+    }
+
+    #[test]
+    fn test_validate_rom() {
+        let mut c8 = create_chip8();
+        // LD VA, 0x2A; 非法 5AB1; JP 0x200
+        c8.load_rom(&[0x6A, 0x2A, 0x5A, 0xB1, 0x12, 0x00]).unwrap();
+        assert_eq!(c8.validate_rom(), [(0x202, 0x5AB1)]);
+    }
+
+    #[test]
+    fn test_load_program() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x6A2A, 0x7A01]);
+        assert_eq!(
+            &c8.memory[ROM_START..ROM_START + 4],
+            &[0x6A, 0x2A, 0x7A, 0x01]
+        );
+        assert_eq!(c8.rom_len, 4);
+        c8.tick().unwrap();
+        c8.tick().unwrap();
+        assert_eq!(c8.v_reg[0xA], 0x2B);
+    }
+
+    #[test]
+    fn test_load_rom_hex() {
+        let mut c8 = create_chip8();
+        c8.load_rom_hex("6a 2a 00 ee").unwrap();
+        assert_eq!(
+            &c8.memory[ROM_START..ROM_START + 4],
+            &[0x6A, 0x2A, 0x00, 0xEE]
+        );
+        assert_eq!(c8.rom_len, 4);
+
+        c8.load_rom_hex("6A2A\n00EE\n").unwrap();
+        assert_eq!(
+            &c8.memory[ROM_START..ROM_START + 4],
+            &[0x6A, 0x2A, 0x00, 0xEE]
+        );
+
+        assert!(matches!(
+            c8.load_rom_hex("6a 2"),
+            Err(LoadError::InvalidHex(3))
+        ));
+        assert!(matches!(
+            c8.load_rom_hex("+f"),
+            Err(LoadError::InvalidHex(0))
+        ));
+        assert!(matches!(
+            c8.load_rom_hex("6a +f"),
+            Err(LoadError::InvalidHex(2))
+        ));
+        assert!(matches!(
+            c8.load_rom_hex("6a zz"),
+            Err(LoadError::InvalidHex(2))
+        ));
+    }
+
+    #[test]
+    fn test_load_at() {
+        let mut c8 = create_chip8();
+        c8.load_at(0x200, &[0x22, 0x00]).unwrap();
+        c8.load_at(0x400, &[0x6A, 0x2A, 0x00, 0xEE]).unwrap();
+        assert_eq!(&c8.memory[0x200..0x202], &[0x22, 0x00]);
+        assert_eq!(&c8.memory[0x400..0x404], &[0x6A, 0x2A, 0x00, 0xEE]);
+
+        assert!(matches!(
+            c8.load_at(0xFFF, &[0x01, 0x02]),
+            Err(LoadError::OutOfBounds {
+                addr: 0xFFF,
+                len: 2
+            })
+        ));
+        assert!(matches!(
+            c8.load_at(0x10, &[0xFF]),
+            Err(LoadError::FontRegion(0x10))
+        ));
+        assert_eq!(c8.memory[0x10], 0x10);
+
+        c8.allow_font_write = true;
+        c8.load_at(0x10, &[0xFF]).unwrap();
+        assert_eq!(c8.memory[0x10], 0xFF);
+    }
+
+    #[test]
+    fn test_memory_fill() {
+        let mut c8 = create_chip8();
+        c8.load_rom(&[0x12, 0x00]).unwrap();
+        c8.set_memory_fill(0xAA);
+        c8.reset();
+        assert_eq!(c8.memory[0x800], 0xAA);
+        assert_eq!(c8.memory[ROM_START], 0xAA);
+        assert_eq!(c8.memory[FONTSET_SIZE], 0xAA);
+        assert_eq!(&c8.memory[..FONTSET_SIZE], &FONTSET[..]);
+    }
+
+    #[test]
+    fn test_memory_range() {
+        let c8 = create_chip8();
+        assert_eq!(c8.memory_range(0..80), Some(&FONTSET[..]));
+        assert_eq!(
+            c8.memory_range(MEMORY_SIZE - 2..MEMORY_SIZE),
+            Some(&[0, 0][..])
+        );
+        assert_eq!(c8.memory_range(MEMORY_SIZE - 1..MEMORY_SIZE + 1), None);
+        assert_eq!(c8.memory_view().len(), MEMORY_SIZE);
+    }
+
+    #[test]
+    fn test_memory_regions() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0x300;
+        let regions = c8.memory_regions();
+        assert_eq!(regions[0], (0..80, "font"));
+        let (rom, label) = &regions[2];
+        assert_eq!(*label, "rom");
+        assert!(rom.contains(&(PROGRAM_START as usize)));
+        assert_eq!(regions[3], (0x300..0x301, "i"));
+        assert_eq!(regions[4], (0x200..0x202, "pc"));
+    }
+
+    #[test]
+    fn test_opcode_execution() {
+        let mut c8 = create_chip8();
+        // 测试 6XNN (LD Vx, NN)
+        c8.memory[0x200] = 0x6A; // Vx = A
+        c8.memory[0x201] = 0xFF; // NN = FF
+        c8.pc = PROGRAM_START;
+
+        let opcode = c8.fetch_opcode();
+        let instruction = Instruction::try_from(opcode).unwrap();
+        c8.execute_instruction(&instruction).unwrap();
+
+        assert_eq!(c8.v_reg[0xA], 0xFF);
+    }
+
+    #[test]
+    fn test_peek_instruction() {
+        let mut c8 = create_chip8();
+        c8.memory[0x200] = 0x6A;
+        c8.memory[0x201] = 0x2A;
+
+        assert!(matches!(
+            c8.peek_instruction(),
+            Ok(Instruction::Ins6XNN(0xA, 0x2A))
+        ));
+        assert_eq!(c8.pc, PROGRAM_START);
+        assert_eq!(c8.v_reg[0xA], 0);
+    }
+
+    #[test]
+    fn test_current_opcode() {
+        let mut c8 = create_chip8();
+        c8.memory[0x200] = 0x5A;
+        c8.memory[0x201] = 0xB1;
+        assert_eq!(c8.current_opcode(), 0x5AB1);
+        assert_eq!(c8.pc, PROGRAM_START);
+        // 错误中带有同样的两个字节
+        assert!(matches!(
+            c8.tick(),
+            Err(EmuError::Instruction(InstructionError::UnknownOpcode(
+                0x5AB1
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_step() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[
+            0x6A2A, // LD VA, 0x2A
+            0x7A01, // ADD VA, 0x01
+        ]);
+
+        let state = c8.step().unwrap();
+        assert!(matches!(
+            state.executed,
+            Some(Instruction::Ins6XNN(0xA, 0x2A))
+        ));
+        assert_eq!(state.v[0xA], 0x2A);
+        assert_eq!(state.pc, 0x202);
+
+        let state = c8.step().unwrap();
+        assert!(matches!(
+            state.executed,
+            Some(Instruction::Ins7XNN(0xA, 0x01))
+        ));
+        assert_eq!(state.v[0xA], 0x2B);
+        assert_eq!(state.pc, 0x204);
+        assert_eq!(state.sp, 0);
+
+        // 停机后单步不再取指
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x00FD, 0x6A2A]);
+        assert!(c8.step().unwrap().executed.is_some());
+        assert!(c8.is_halted());
+        let state = c8.step().unwrap();
+        assert!(state.executed.is_none());
+        assert_eq!(state.pc, 0x202);
+        assert_eq!(state.v[0xA], 0);
+        assert_eq!(c8.cycle_count, 1);
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_trace_logging() {
+        extern crate std;
+        use std::{string::String, sync::Mutex};
+
+        static RECORDS: Mutex<Vec<(log::Level, String)>> = Mutex::new(Vec::new());
+
+        struct TestLogger;
+
+        impl log::Log for TestLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                use std::string::ToString;
+                let msg = record.args().to_string();
+                RECORDS.lock().unwrap().push((record.level(), msg));
+            }
+
+            fn flush(&self) {}
+        }
+
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut c8 = create_chip8();
+        c8.load_rom(&[0x6A, 0x2A]).unwrap();
+        c8.tick().unwrap();
+
+        let records = RECORDS.lock().unwrap();
+        assert!(
+            records
+                .iter()
+                .any(|(level, msg)| *level == log::Level::Trace
+                    && msg == "0200: 6a2a Ins6XNN(10, 42)")
+        );
+    }
+
+    #[test]
+    fn test_sys_instruction() {
+        let mut c8 = create_chip8();
+        c8.load_rom(&[0x01, 0x23, 0x01, 0x23]).unwrap();
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, 0x202);
+        assert_eq!(c8.v_reg, [0; NUM_REGISTERS]);
+
+        c8.sys_hook = Some(|c8, nnn| c8.i_reg = nnn);
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, 0x204);
+        assert_eq!(c8.i_reg, 0x123);
+    }
+
+    #[test]
+    fn test_jump_instruction() {
+        let mut c8 = create_chip8();
+        // 测试 1NNN (JP)
+        c8.memory[0x200] = 0x12;
+        c8.memory[0x201] = 0x30; // JP 0x230
+        c8.pc = PROGRAM_START;
+
+        let opcode = c8.fetch_opcode();
+        let instruction = Instruction::try_from(opcode).unwrap();
+        c8.execute_instruction(&instruction).unwrap();
+
+        assert_eq!(c8.pc, 0x230);
+    }
+
+    #[test]
+    fn test_ticks_iterator() {
+        let mut c8 = create_chip8();
+        c8.load_rom(&[0x6A, 0x01, 0x7A, 0x01, 0x7A, 0x01, 0x12, 0x06])
+            .unwrap();
+        let results: Vec<_> = c8.ticks().take(3).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(c8.v_reg[0xA], 3);
+
+        // 运行直到停机
+        let count = c8
+            .ticks()
+            .take_while(|r| matches!(r, Ok(result) if !result.halted))
+            .count();
+        assert_eq!(count, 0);
+        assert!(c8.is_halted());
+    }
+
+    #[test]
+    fn test_spin_loop_halt() {
+        let mut c8 = create_chip8();
+        c8.memory[0x200] = 0x12;
+        c8.memory[0x201] = 0x02; // JP 0x202
+        c8.memory[0x202] = 0x12;
+        c8.memory[0x203] = 0x02; // JP 0x202
+
+        assert!(!c8.tick().unwrap().halted);
+        assert!(!c8.is_halted());
+        assert!(c8.tick().unwrap().halted);
+        assert!(c8.is_halted());
+        assert_eq!(c8.pc, 0x202);
+
+        c8.memory[0x202] = 0x6A; // 停机后不再执行
+        assert!(c8.tick().unwrap().halted);
+        assert_eq!(c8.v_reg[0xA], 0);
+    }
+
+    #[test]
+    fn test_exit_instruction() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x00FD, 0x6A2A]);
+        assert!(c8.tick().unwrap().halted);
+        assert!(c8.is_halted());
+        assert_eq!(c8.pc, 0x202);
+
+        assert!(c8.tick().unwrap().halted);
+        assert_eq!(c8.pc, 0x202);
+        assert_eq!(c8.v_reg[0xA], 0);
+    }
+
+    #[test]
+    fn test_jump_with_offset_masked() {
+        let mut c8 = create_chip8();
+        c8.v_reg[0] = 0x10;
+        c8.execute_instruction(&Instruction::InsBNNN(0x300))
+            .unwrap();
+        assert_eq!(c8.pc, 0x310);
+
+        c8.v_reg[0] = 0xFF;
+        c8.execute_instruction(&Instruction::InsBNNN(0xFFF))
+            .unwrap();
+        assert_eq!(c8.pc, 0x0FE);
+    }
+
+    #[test]
+    fn test_stack_operations() {
+        let mut c8 = create_chip8();
+        // 测试 2NNN (CALL)
+        c8.pc = 0x200;
+        c8.memory[0x200] = 0x23;
+        c8.memory[0x201] = 0x00; // CALL 0x300
+
+        // 执行CALL
+        let opcode = c8.fetch_opcode();
+        let instruction = Instruction::try_from(opcode).unwrap();
+        c8.execute_instruction(&instruction).unwrap();
+
+        assert_eq!(c8.stack[0], 0x202); // 返回地址
+        assert_eq!(c8.stack_pointer, 1);
+        assert_eq!(c8.pc, 0x300);
+
+        // 测试 00EE (RET)
+        c8.memory[0x300] = 0x00;
+        c8.memory[0x301] = 0xEE;
+        c8.pc = 0x300;
+
+        let opcode = c8.fetch_opcode();
+        let instruction = Instruction::try_from(opcode).unwrap();
+        c8.execute_instruction(&instruction).unwrap();
+
+        assert_eq!(c8.stack_pointer, 0);
+        assert_eq!(c8.pc, 0x202);
+    }
+
+    #[test]
+    fn test_cycles_elapsed() {
+        let mut c8 = create_chip8();
+        c8.track_cycles = true;
+        c8.memory[0x200] = 0x6A;
+        c8.memory[0x201] = 0x01; // LD VA, 0x01
+        c8.memory[0x202] = 0xFA;
+        c8.memory[0x203] = 0x33; // BCD VA
+        c8.tick().unwrap();
+        c8.tick().unwrap();
+        assert_eq!(
+            c8.cycles_elapsed,
+            (instruction::cost(&Instruction::Ins6XNN(0xA, 0x01))
+                + instruction::cost(&Instruction::InsFX33(0xA))) as u64
+        );
+    }
+
+    #[test]
+    fn test_register_file() {
+        let mut c8 = create_chip8();
+        let regs: [u8; NUM_REGISTERS] = core::array::from_fn(|i| (i * 0x11) as u8);
+        c8.set_registers(regs);
+        assert_eq!(c8.registers(), regs);
+
+        c8.execute_instruction(&Instruction::Ins8XY0(0, 0xE))
+            .unwrap();
+        assert_eq!(c8.registers()[0], 0xEE);
+    }
+
+    #[test]
+    fn test_timer_round_trip() {
+        let mut c8 = create_chip8();
+        c8.v_reg[0] = 42;
+        c8.v_reg[2] = 7;
+        c8.execute_instruction(&Instruction::InsFX15(0)).unwrap();
+        c8.execute_instruction(&Instruction::InsFX07(1)).unwrap();
+        assert_eq!(c8.v_reg[1], c8.v_reg[0]);
+        c8.execute_instruction(&Instruction::InsFX18(2)).unwrap();
+        assert_eq!(c8.timers(), (42, 7));
+    }
+
+    #[test]
+    fn test_tick_timers_by() {
+        let mut c8 = create_chip8();
+        c8.delay_timer = 5;
+        c8.sound_timer = 2;
+        c8.tick_timers_by(3);
+        assert_eq!(c8.delay_timer, 2);
+        assert_eq!(c8.sound_timer, 0);
+    }
+
+    #[test]
+    fn test_update_timers() {
+        struct MockTime(u64);
+
+        impl TimeSource for MockTime {
+            fn elapsed_ms(&mut self) -> u64 {
+                self.0
+            }
+        }
+
+        let mut c8 = create_chip8();
+        c8.delay_timer = 10;
+        c8.sound_timer = 10;
+        c8.update_timers(&mut MockTime(33));
+        assert_eq!(c8.delay_timer, 8);
+        assert_eq!(c8.sound_timer, 8);
+
+        // 误差累计: 6 次 10ms 共 60ms, 约 3.6 次
+        for _ in 0..6 {
+            c8.update_timers(&mut MockTime(10));
+        }
+        assert_eq!(c8.delay_timer, 4);
+    }
+
+    #[test]
+    fn test_update_timers_sub_frame_polling() {
+        struct MockTime(u64);
+
+        impl TimeSource for MockTime {
+            fn elapsed_ms(&mut self) -> u64 {
+                self.0
+            }
+        }
+
+        // 每 5ms 轮询一次, 第一次轮询不足一帧, 第二次 DXYN 仍需等待
+        let mut c8 = create_chip8();
+        c8.quirks.display_wait = true;
+        c8.load_program(&[0xD015, 0xD015]);
+        c8.tick().unwrap();
+        c8.update_timers(&mut MockTime(5));
+        assert!(c8.tick().unwrap().awaiting_vblank);
+        assert_eq!(c8.pc, 0x202);
+
+        // 每条指令之间轮询 1ms 时 runaway 检测仍然有效
+        let mut c8 = create_chip8();
+        c8.runaway_threshold = Some(5);
+        c8.load_program(&[0x7001, 0x1200]);
+        for _ in 0..4 {
+            c8.update_timers(&mut MockTime(1));
+            assert!(!c8.tick().unwrap().runaway);
+        }
+        c8.update_timers(&mut MockTime(1));
+        assert!(c8.tick().unwrap().runaway);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut c8 = create_chip8();
+        c8.memory[0x200] = 0x6A;
+        c8.memory[0x201] = 0x2A;
+        c8.delay_timer = 5;
+        c8.pause();
+        assert!(c8.is_paused());
+
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, PROGRAM_START);
+        assert_eq!(c8.v_reg[0xA], 0);
+
+        // 默认暂停时计时器仍然工作
+        c8.tick_timer();
+        assert_eq!(c8.delay_timer, 4);
+        c8.pause_timers = true;
+        c8.tick_timer();
+        assert_eq!(c8.delay_timer, 4);
+
+        c8.resume();
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, PROGRAM_START + 2);
+        assert_eq!(c8.v_reg[0xA], 0x2A);
+    }
+
+    #[test]
+    fn test_opcode_histogram() {
+        let mut c8 = create_chip8();
+        c8.profiling = true;
+        c8.load_program(&[
+            0x7001, // ADD V0, 1
+            0x1200, // JP 0x200
+        ]);
+        for _ in 0..10 {
+            c8.tick().unwrap();
+        }
+        let histogram = c8.opcode_histogram();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram["7XNN"], 5);
+        assert_eq!(histogram["1NNN"], 5);
+
+        c8.reset();
+        assert!(c8.opcode_histogram().is_empty());
+    }
+
+    #[test]
+    fn test_set_rng() {
+        let mut c8 = create_chip8();
+        c8.execute_instruction(&Instruction::InsCXNN(0, 0xFF))
+            .unwrap();
+        let default_value = c8.v_reg[0];
+
+        c8.set_rng(LinearCongruentialGenerator { seed: 1 });
+        c8.execute_instruction(&Instruction::InsCXNN(0, 0xFF))
+            .unwrap();
+        let expected = LinearCongruentialGenerator { seed: 1 }.next().unwrap() as u8;
+        assert_eq!(c8.v_reg[0], expected);
+        assert_ne!(c8.v_reg[0], default_value);
+
+        c8.rng_mut().seed = 1;
+        c8.execute_instruction(&Instruction::InsCXNN(1, 0xFF))
+            .unwrap();
+        assert_eq!(c8.v_reg[1], expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_timing_drift() {
+        use std::time::Duration;
+
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x1202, 0x1200]);
+        for _ in 0..700 {
+            c8.tick().unwrap();
+        }
+        // 700Hz 下 700 条指令相当于 1 秒
+        assert_eq!(c8.timing_drift_ms(700, Duration::from_millis(1000)), 0.0);
+        assert_eq!(c8.timing_drift_ms(700, Duration::from_millis(1250)), -250.0);
+        assert_eq!(c8.timing_drift_ms(500, Duration::from_millis(1000)), 400.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_bench_draw() {
+        let mut c8 = create_chip8();
+        c8.load_fontset();
+        c8.i_reg = 0x300;
+        c8.v_reg[0xF] = 7;
+        c8.bench_draw(10);
+        assert_eq!(c8.i_reg, 0x300);
+        assert_eq!(c8.v_reg[0xF], 7);
+        assert!(c8.get_display().iter().all(|&p| !p));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_bench_draw_display_wait() {
+        let mut c8 = create_chip8();
+        c8.quirks.display_wait = true;
+        c8.profiling = true;
+        c8.screen.set(1, 1, true);
+        c8.take_dirty_rect();
+        c8.bench_draw(5);
+        assert_eq!(c8.pc, PROGRAM_START);
+        assert!(!c8.draw_flag);
+        assert_eq!(c8.redundant_draws(), 0);
+        assert_eq!(c8.take_dirty_rect(), None);
+        assert_eq!(c8.get_display().iter().filter(|&&p| p).count(), 1);
+        // 第一条 DXYN 不需要等待
+        c8.load_program(&[0xD015]);
+        assert!(!c8.tick().unwrap().awaiting_vblank);
+    }
+
+    #[test]
+    fn test_key_script_replay() {
+        let mut c8 = create_chip8();
+        c8.memory[0x200] = 0x12;
+        c8.memory[0x201] = 0x02; // JP 0x202
+        c8.memory[0x202] = 0x12;
+        c8.memory[0x203] = 0x00; // JP 0x200
+        c8.key_script.push(5, 0x5, true);
+
+        while c8.cycle_count < 5 {
+            c8.apply_key_events_at(c8.cycle_count);
+            assert!(!c8.keypad[0x5]);
+            c8.tick().unwrap();
+        }
+        c8.apply_key_events_at(c8.cycle_count);
+        assert!(c8.keypad[0x5]);
+    }
+
+    #[test]
+    fn test_stack_underflow() {
+        let mut c8 = create_chip8();
+        c8.load_rom(&[0x00, 0xEE]).unwrap();
+        assert!(matches!(c8.tick(), Err(EmuError::StackUnderflow)));
+        assert_eq!(c8.stack_pointer, 0);
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::Ins00EE),
+            Err(EmuError::StackUnderflow)
+        ));
+        assert_eq!(c8.stack_pointer, 0);
+
+        // stack_pointer 超出调用栈
+        c8.stack_pointer = STACK_SIZE as u16 + 4;
+        let pc = c8.pc;
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::Ins00EE),
+            Err(EmuError::BadReturn(20))
+        ));
+        assert_eq!(c8.stack_pointer, STACK_SIZE as u16 + 4);
+        assert_eq!(c8.pc, pc);
+    }
+
+    #[test]
+    fn test_huge_stack_depth() {
+        let depth = u16::MAX as usize + 10;
+        let mut c8: Chip8<_> =
+            Chip8::with_stack_depth(LinearCongruentialGenerator::default(), depth);
+        c8.stack_pointer = u16::MAX;
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::Ins2NNN(0x300)),
+            Err(EmuError::StackOverflow)
+        ));
+        assert_eq!(c8.stack_pointer, u16::MAX);
+    }
+
+    #[test]
+    fn test_stack_depth() {
+        let mut c8: Chip8<_> = Chip8::with_stack_depth(LinearCongruentialGenerator::default(), 4);
+        // 每个子程序都调用下一个: 0x200 -> 0x300 -> 0x310 -> 0x320 -> 0x330 -> 0x340
+        c8.load_at(0x200, &[0x23, 0x00]).unwrap();
+        for addr in [0x300, 0x310, 0x320, 0x330] {
+            let target = addr + 0x10;
+            c8.load_at(addr, &[0x20 | (target >> 8) as u8, target as u8])
+                .unwrap();
+        }
+        for _ in 0..4 {
+            c8.tick().unwrap();
+        }
+        assert_eq!(c8.stack_pointer, 4);
+        assert_eq!(c8.pc, 0x330);
+        assert!(matches!(c8.tick(), Err(EmuError::StackOverflow)));
+    }
+
+    #[test]
+    fn test_rng_log_replay() {
+        let program = [0xC0FF, 0xC1FF, 0xC20F, 0x1200];
+        let run = |c8: &mut Chip8<LinearCongruentialGenerator>| {
+            c8.record_rng = true;
+            c8.load_program(&program);
+            for _ in 0..20 {
+                c8.tick().unwrap();
+            }
+        };
+
+        let mut first = Chip8::new(LinearCongruentialGenerator { seed: 7 });
+        run(&mut first);
+        let mut second = Chip8::new(LinearCongruentialGenerator { seed: 7 });
+        run(&mut second);
+        assert_eq!(first.rng_log().len(), 15);
+        assert_eq!(first.rng_log(), second.rng_log());
+        assert_eq!(first.v_reg, second.v_reg);
+
+        // 不同种子, 回放记录后结果一致
+        let mut replayed = Chip8::new(LinearCongruentialGenerator { seed: 1 });
+        replayed.replay_rng(first.rng_log());
+        run(&mut replayed);
+        assert_eq!(replayed.rng_log(), first.rng_log());
+        assert_eq!(replayed.v_reg, first.v_reg);
+    }
+
+    #[test]
+    fn test_clear_display_and_keys() {
+        let mut c8 = create_chip8();
+        c8.screen.set(3, 3, true);
+        c8.clear_display();
+        assert!(c8.get_display().iter().all(|&p| !p));
+        assert!(c8.draw_flag);
+
+        c8.keypress(0x1, true);
+        c8.keypress(0xF, true);
+        c8.clear_keys();
+        assert!(c8.keypad.iter().all(|&k| !k));
+    }
+
+    #[test]
+    fn test_timer_decrement() {
+        let mut c8 = create_chip8();
+        c8.delay_timer = 5;
+        c8.sound_timer = 3;
+
+        c8.tick_timer();
+        assert_eq!(c8.delay_timer, 4);
+        assert_eq!(c8.sound_timer, 2);
+
+        c8.tick_timer();
+        assert_eq!(c8.delay_timer, 3);
+        assert_eq!(c8.sound_timer, 1);
+    }
+
+    #[test]
+    fn test_screen_dimensions() {
+        let c8 = create_chip8();
+        assert_eq!(c8.screen_width(), 64);
+        assert_eq!(c8.screen_height(), 32);
+        assert_eq!(
+            c8.get_display().len(),
+            crate::SCREEN_WIDTH * crate::SCREEN_HEIGHT
+        );
+    }
+
+    #[test]
+    fn test_display_diff() {
+        let mut c8 = create_chip8();
+        c8.execute_instruction(&Instruction::Ins00E0).unwrap();
+        c8.i_reg = 0x300;
+        c8.memory[0x300] = 0b1110_0000;
+        c8.v_reg[0] = 2;
+        c8.v_reg[1] = 1;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+
+        let prev = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
+        let row = SCREEN_WIDTH as u16;
+        assert_eq!(
+            c8.display_diff(&prev),
+            [(row + 2, true), (row + 3, true), (row + 4, true)]
+        );
+        assert!(c8.display_diff(&c8.get_display()).is_empty());
+    }
+
+    #[test]
+    fn test_render_rgba() {
+        let mut c8 = create_chip8();
+        let on = [0xFF, 0xFF, 0xFF, 0xFF];
+        let off = [0, 0, 0, 0xFF];
+        c8.screen.set(1, 0, true);
+
+        let buf = c8.render_rgba(1, on, off);
+        assert_eq!(buf.len(), SCREEN_WIDTH * SCREEN_HEIGHT * 4);
+        assert_eq!(&buf[0..4], &off);
+        assert_eq!(&buf[4..8], &on);
+
+        let buf = c8.render_rgba(2, on, off);
+        let stride = SCREEN_WIDTH * 2 * 4;
+        assert_eq!(buf.len(), stride * SCREEN_HEIGHT * 2);
+        // (1, 0) 放大为 x=2..4, y=0..2
+        assert_eq!(&buf[8..16], &[on, on].concat()[..]);
+        assert_eq!(&buf[stride + 8..stride + 16], &[on, on].concat()[..]);
+        assert_eq!(&buf[stride * 2 + 8..stride * 2 + 12], &off);
+
+        assert!(c8.render_rgba(usize::MAX, on, off).is_empty());
+        assert!(c8.render_rgba(usize::MAX / 64, on, off).is_empty());
+    }
+
+    #[test]
+    fn test_draw_instruction() {
+        let mut c8 = create_chip8();
+        // 初始化精灵数据
+        c8.i_reg = 0x00;
+        c8.memory[0] = 0b11110000; // 4像素宽
+
+        // 设置坐标
+        c8.v_reg[0] = 0; // V0 = X
+        c8.v_reg[1] = 0; // V1 = Y
+
+        // 执行DXYN（D015）
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+
+        // 验证第一行像素
+        assert!(c8.screen.get(0, 0)); // 第1列
+        assert!(c8.screen.get(1, 0)); // 第2列
+        assert!(c8.screen.get(2, 0)); // 第3列
+        assert!(c8.screen.get(3, 0)); // 第4列
+        assert_eq!(c8.v_reg[0xF], 0); // 无碰撞
+    }
+
+    #[test]
+    fn test_dirty_rect() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0x300;
+        c8.memory[0x300] = 0b0110_0000;
+        c8.memory[0x301] = 0b1111_0000;
+        c8.v_reg[0] = 10;
+        c8.v_reg[1] = 5;
+        assert_eq!(c8.take_dirty_rect(), None);
+
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 2))
+            .unwrap();
+        assert_eq!(c8.take_dirty_rect(), Some((10, 5, 13, 6)));
+        assert_eq!(c8.take_dirty_rect(), None);
+    }
+
+    #[test]
+    fn test_collision_pixels() {
+        let mut c8 = create_chip8();
+        c8.track_collisions = true;
+        c8.i_reg = 0x300;
+        c8.memory[0x300] = 0b1111_0000;
+        c8.memory[0x301] = 0b0011_1100;
+        c8.v_reg[0] = 4;
+        c8.v_reg[1] = 3;
+
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(c8.last_collision_pixels.is_empty());
+
+        // 第二个精灵从 0x301 开始, 与第一个在 x=6,7 重叠
+        c8.i_reg = 0x301;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert_eq!(c8.last_collision_pixels, [(6, 3), (7, 3)]);
+        assert_eq!(c8.v_reg[0xF], 1);
+
+        // 每次 DXYN 开始时清空
+        c8.v_reg[1] = 10;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(c8.last_collision_pixels.is_empty());
+    }
+
+    #[test]
+    fn test_draw_sprite_out_of_memory() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0xFFE;
+        c8.v_reg[0xF] = 0x7;
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5)),
+            Err(EmuError::MemoryOutOfBounds(0xFFE))
+        ));
+        assert!(c8.get_display().iter().all(|&p| !p));
+        assert_eq!(c8.v_reg[0xF], 0x7);
+
+        // 刚好读到最后一个字节
+        c8.memory[0xFFE] = 0x80;
+        c8.memory[0xFFF] = 0x80;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 2))
+            .unwrap();
+        assert!(c8.screen.get(0, 1));
+    }
+
+    #[test]
+    fn test_draw_overwrite_mode() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0x300;
+        c8.memory[0x300] = 0b1010_0000;
+        c8.draw_mode = DrawMode::Overwrite;
+
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(c8.screen.get(0, 0));
+        assert!(!c8.screen.get(1, 0));
+        assert!(c8.screen.get(2, 0));
+        assert_eq!(c8.v_reg[0xF], 0);
+
+        // XOR 模式下第二次绘制会擦除
+        c8.draw_mode = DrawMode::Xor;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(!c8.screen.get(0, 0));
+        assert!(!c8.screen.get(2, 0));
+        assert_eq!(c8.v_reg[0xF], 1);
+    }
+
+    #[test]
+    fn test_draw_vf_reset_quirk() {
+        let mut c8 = create_chip8();
+        // 右下角只能显示第一列, 而精灵第一列为 0, 相当于完全在屏幕外
+        c8.i_reg = 0x300;
+        c8.memory[0x300] = 0b0111_1111;
+        c8.v_reg[0] = (SCREEN_WIDTH - 1) as u8;
+        c8.v_reg[1] = (SCREEN_HEIGHT - 1) as u8;
+
+        c8.v_reg[0xF] = 1;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 0);
+        assert!(c8.get_display().iter().all(|&p| !p));
+
+        // N=0 同样清零
+        c8.v_reg[0xF] = 1;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 0))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 0);
+
+        // 关闭 quirk 时 VF 保持不变
+        c8.quirks.vf_reset_on_draw = false;
+        c8.v_reg[0xF] = 1;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 1);
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 0))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 1);
+    }
+
+    #[test]
+    fn test_tick_result() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[
+            0xD015, // DRW V0, V1, 5
+            0xF00A, // LD V0, K
+        ]);
+
+        let result = c8.tick().unwrap();
+        assert!(result.drew);
+        assert!(!result.awaiting_key);
+
+        let result = c8.tick().unwrap();
+        assert!(!result.drew);
+        assert!(result.awaiting_key);
+        assert_eq!(c8.pc, 0x202);
+
+        c8.sound_timer = 2;
+        c8.keypress(0x3, true);
+        let result = c8.tick().unwrap();
+        assert!(!result.awaiting_key);
+        assert!(result.beep);
+        assert_eq!(c8.v_reg[0], 0x3);
+    }
+
+    #[test]
+    fn test_protect_interpreter_region() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0x100;
+        c8.v_reg[0] = 0xAA;
+        c8.execute_instruction(&Instruction::InsFX55(0)).unwrap();
+        assert_eq!(c8.memory[0x100], 0xAA);
+
+        c8.protect_interpreter_region = true;
+        c8.i_reg = 0x100;
+        c8.v_reg[0] = 0xBB;
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::InsFX55(0)),
+            Err(EmuError::ProtectedWrite(0x100))
+        ));
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::InsFX33(0)),
+            Err(EmuError::ProtectedWrite(0x100))
+        ));
+        assert_eq!(c8.memory[0x100], 0xAA);
+        assert_eq!(c8.i_reg, 0x100);
+
+        c8.i_reg = PROGRAM_START;
+        c8.execute_instruction(&Instruction::InsFX55(0)).unwrap();
+        assert_eq!(c8.memory[0x200], 0xBB);
+    }
+
+    #[test]
+    fn test_display_wait_and_turbo() {
+        let mut c8 = create_chip8();
+        c8.quirks.display_wait = true;
+        c8.load_rom(&[0xD0, 0x11, 0xD0, 0x11]).unwrap();
+
+        assert!(!c8.tick().unwrap().awaiting_vblank);
+        let result = c8.tick().unwrap();
+        assert!(result.awaiting_vblank);
+        assert!(!result.drew);
+        assert_eq!(c8.pc, 0x202);
+
+        c8.tick_timer();
+        assert!(!c8.tick().unwrap().awaiting_vblank);
+        assert_eq!(c8.pc, 0x204);
+
+        c8.turbo = true;
         c8.pc = PROGRAM_START;
+        assert!(!c8.tick().unwrap().awaiting_vblank);
+        let result = c8.tick().unwrap();
+        assert!(!result.awaiting_vblank);
+        assert!(result.drew);
+        assert_eq!(c8.pc, 0x204);
+    }
+
+    #[test]
+    fn test_self_modify_warning() {
+        let mut c8 = create_chip8();
+        c8.warn_self_modify = true;
+        c8.load_program(&[
+            0xA300, // LD I, 0x300
+            0xF055, // LD [I], V0
+            0xA202, // LD I, 0x202
+            0xF055, // LD [I], V0
+        ]);
+        assert!(!c8.tick().unwrap().self_modified);
+        // 0x300 不在 ROM 内
+        assert!(!c8.tick().unwrap().self_modified);
+        assert!(!c8.tick().unwrap().self_modified);
+        assert!(c8.tick().unwrap().self_modified);
+    }
+
+    #[test]
+    fn test_bcd() {
+        assert_eq!(bcd(0), [0, 0, 0]);
+        assert_eq!(bcd(9), [0, 0, 9]);
+        assert_eq!(bcd(128), [1, 2, 8]);
+        assert_eq!(bcd(255), [2, 5, 5]);
+
+        let mut c8 = create_chip8();
+        c8.i_reg = 0x300;
+        c8.v_reg[0] = 255;
+        c8.execute_instruction(&Instruction::InsFX33(0)).unwrap();
+        assert_eq!(&c8.memory[0x300..0x303], &[2, 5, 5]);
+    }
+
+    #[test]
+    fn test_arithmetic_instructions() {
+        let mut c8 = create_chip8();
+
+        // 测试8XY4（ADD）
+        c8.v_reg[0] = 0xFE;
+        c8.v_reg[1] = 0x03;
+        c8.execute_instruction(&Instruction::Ins8XY4(0, 1)).unwrap();
+        assert_eq!(c8.v_reg[0], 0x01); // 溢出
+        assert_eq!(c8.v_reg[0xF], 1); // 进位标志
+
+        // 测试8XY5（SUB）
+        c8.v_reg[0] = 0x05;
+        c8.v_reg[1] = 0x03;
+        c8.execute_instruction(&Instruction::Ins8XY5(0, 1)).unwrap();
+        assert_eq!(c8.v_reg[0], 0x02);
+        assert_eq!(c8.v_reg[0xF], 1); // 无借位
+    }
+
+    #[test]
+    fn test_add_immediate_to_vf() {
+        let mut c8 = create_chip8();
+        c8.v_reg[0xF] = 0xFE;
+        c8.execute_instruction(&Instruction::Ins7XNN(0xF, 0x03))
+            .unwrap();
+        // 结果回绕, 不会被进位覆盖
+        assert_eq!(c8.v_reg[0xF], 0x01);
+
+        c8.v_reg[0] = 0xFF;
+        c8.v_reg[0xF] = 0x42;
+        c8.execute_instruction(&Instruction::Ins7XNN(0, 0x01))
+            .unwrap();
+        assert_eq!(c8.v_reg[0], 0x00);
+        assert_eq!(c8.v_reg[0xF], 0x42);
+    }
+
+    #[test]
+    fn test_logic_vf_quirk() {
+        let ops = [
+            Instruction::Ins8XY1(0, 1),
+            Instruction::Ins8XY2(0, 1),
+            Instruction::Ins8XY3(0, 1),
+        ];
+        for logic_clears_vf in [true, false] {
+            let mut c8 = create_chip8();
+            c8.quirks.logic_clears_vf = logic_clears_vf;
+            for op in &ops {
+                c8.v_reg[0] = 0b1100;
+                c8.v_reg[1] = 0b1010;
+                c8.v_reg[0xF] = 0x7;
+                c8.execute_instruction(op).unwrap();
+                let expected = if logic_clears_vf { 0 } else { 0x7 };
+                assert_eq!(c8.v_reg[0xF], expected);
+            }
+            // 最后一条是 XOR
+            assert_eq!(c8.v_reg[0], 0b0110);
+        }
+    }
+
+    #[test]
+    fn test_i_overflow_quirk() {
+        let mut c8 = create_chip8();
+        c8.i_reg = 0xFFF;
+        c8.v_reg[0] = 1;
+        c8.v_reg[0xF] = 0x7;
+        c8.execute_instruction(&Instruction::InsFX1E(0)).unwrap();
+        assert_eq!(c8.i_reg, 0x000);
+        assert_eq!(c8.v_reg[0xF], 0x7);
+
+        c8.quirks.i_overflow_sets_vf = true;
+        c8.i_reg = 0xFFF;
+        c8.execute_instruction(&Instruction::InsFX1E(0)).unwrap();
+        assert_eq!(c8.i_reg, 0x000);
+        assert_eq!(c8.v_reg[0xF], 1);
+
+        c8.i_reg = 0x200;
+        c8.execute_instruction(&Instruction::InsFX1E(0)).unwrap();
+        assert_eq!(c8.v_reg[0xF], 0);
+    }
+
+    #[test]
+    fn test_fx1e_masks_to_12_bits() {
+        let mut c8 = create_chip8();
+        c8.load_fontset();
+        c8.i_reg = 0xFFF;
+        c8.v_reg[0] = 5;
+        c8.execute_instruction(&Instruction::InsFX1E(0)).unwrap();
+        assert_eq!(c8.i_reg, 0x004);
+        // 之后读取精灵不会越界
+        c8.execute_instruction(&Instruction::InsDXYN(1, 1, 15))
+            .unwrap();
+        assert!(c8.screen.get(0, 0));
+    }
+
+    #[test]
+    fn test_keyboard_instructions() {
+        let mut c8 = create_chip8();
+
+        // 测试EXA1（SKNP）
+        c8.v_reg[0] = 0xA; // 检查按键A（hex key）
+        c8.keypad[0xA] = false;
+        c8.execute_instruction(&Instruction::InsEXA1(0)).unwrap();
+        assert_eq!(c8.pc, 0x200 + 2); // 应该跳过
+
+        // 测试FX0A（等待按键）
+        c8.execute_instruction(&Instruction::InsFX0A(0)).unwrap();
+        assert_eq!(c8.pc, 0x200); // 没有按键, 继续等待
+        c8.keypad[0x5] = true;
+        c8.execute_instruction(&Instruction::InsFX0A(0)).unwrap();
+        assert_eq!(c8.v_reg[0], 0x5);
+    }
+
+    #[test]
+    fn test_wait_key_requires_fresh_press() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0xF00A]);
+        c8.keypress(0x5, true);
+
+        // 进入 FX0A 前已按住的键不会结束等待
+        assert!(c8.tick().unwrap().awaiting_key);
+        assert!(c8.tick().unwrap().awaiting_key);
+        assert_eq!(c8.pc, PROGRAM_START);
+
+        // 松开后再次按下才算
+        c8.keypress(0x5, false);
+        assert!(c8.tick().unwrap().awaiting_key);
+        c8.keypress(0x5, true);
+        assert!(!c8.tick().unwrap().awaiting_key);
+        assert_eq!(c8.v_reg[0], 0x5);
+        assert_eq!(c8.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn test_super_resolution_draw() {
+        let mut c8: Chip8Super<_> = Chip8::new(LinearCongruentialGenerator::default());
+        c8.load_fontset();
+        assert_eq!((c8.screen_width(), c8.screen_height()), (128, 64));
+        c8.v_reg[0] = 100;
+        c8.v_reg[1] = 40;
+        c8.i_reg = 0; // 字符 "0" 的第一行为 0xF0
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        // 经典分辨率下 x=100 会被回绕到 36
+        assert!(c8.screen.get(100, 40));
+        assert!(c8.screen.get(103, 40));
+        assert!(!c8.screen.get(36, 40));
+        assert_eq!(c8.get_display().len(), 128 * 64);
+        assert!(c8.get_display()[100 + 40 * 128]);
+    }
+
+    #[test]
+    fn test_keypress_edge() {
+        let mut c8 = create_chip8();
+        assert!(c8.keypress_edge(0x3, true));
+        assert!(!c8.keypress_edge(0x3, true));
+        assert!(c8.keypad[0x3]); // 按住时状态保持, EX9E 仍然有效
+        assert!(!c8.keypress_edge(0x3, false));
+        assert!(c8.keypress_edge(0x3, true));
+    }
+
+    #[test]
+    fn test_execute_opcode() {
+        let mut c8 = create_chip8();
+        c8.execute_opcode(0x6A2A).unwrap();
+        assert_eq!(c8.v_reg[0xA], 0x2A);
+        assert_eq!(c8.pc, PROGRAM_START);
+        c8.execute_opcode(0x1300).unwrap();
+        assert_eq!(c8.pc, 0x300);
+        assert!(matches!(
+            c8.execute_opcode(0xF0FF),
+            Err(EmuError::Instruction(InstructionError::UnknownOpcode(
+                0xF0FF
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_keypress_mapped() {
+        let mut c8 = create_chip8();
+        c8.keypress_mapped(0x2, true);
+        assert!(c8.keypad[0x2]); // 默认为恒等映射
+        c8.keypress_mapped(0x2, false);
 
-        let opcode = c8.fetch_opcode();
-        let instruction = Instruction::try_from(opcode).unwrap();
-        c8.execute_instruction(&instruction);
+        c8.key_map.swap(0x1, 0xC);
+        c8.keypress_mapped(0x1, true);
+        assert!(c8.keypad[0xC]);
+        assert!(!c8.keypad[0x1]);
+    }
 
-        assert_eq!(c8.v_reg[0xA], 0xFF);
+    #[test]
+    fn test_draw_flag_set_once_per_draw() {
+        let mut c8 = create_chip8();
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        assert!(c8.draw_flag);
+
+        // N=0 没有绘制任何行
+        c8.draw_flag = false;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 0))
+            .unwrap();
+        assert!(!c8.draw_flag);
     }
 
     #[test]
-    fn test_jump_instruction() {
+    fn test_shift_and_load_store_quirks() {
         let mut c8 = create_chip8();
-        // 测试 1NNN (JP)
-        c8.memory[0x200] = 0x12;
-        c8.memory[0x201] = 0x30; // JP 0x230
-        c8.pc = PROGRAM_START;
+        c8.quirks.shift_uses_vy = false;
+        c8.v_reg[0] = 0b0000_0011;
+        c8.v_reg[1] = 0b1000_0000;
+        c8.execute_instruction(&Instruction::Ins8XY6(0, 1)).unwrap();
+        assert_eq!(c8.v_reg[0], 0b0000_0001);
+        assert_eq!(c8.v_reg[0xF], 1);
 
-        let opcode = c8.fetch_opcode();
-        let instruction = Instruction::try_from(opcode).unwrap();
-        c8.execute_instruction(&instruction);
+        c8.quirks.load_store_increments_i = false;
+        c8.i_reg = 0x300;
+        c8.execute_instruction(&Instruction::InsFX55(2)).unwrap();
+        assert_eq!(c8.i_reg, 0x300);
+        c8.execute_instruction(&Instruction::InsFX65(2)).unwrap();
+        assert_eq!(c8.i_reg, 0x300);
+    }
 
-        assert_eq!(c8.pc, 0x230);
+    #[test]
+    fn test_last_draw_was_font() {
+        let mut c8 = create_chip8();
+        assert!(!c8.last_draw_was_font());
+        c8.v_reg[2] = 5;
+        c8.execute_instruction(&Instruction::InsFX29(2)).unwrap();
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        assert!(c8.last_draw_was_font());
+
+        c8.i_reg = 0x300;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(!c8.last_draw_was_font());
     }
 
     #[test]
-    fn test_stack_operations() {
+    fn test_reseed_from_rom() {
+        fn random_bytes(rom: &[u8]) -> Vec<u8> {
+            let mut c8 = create_chip8();
+            c8.load_rom(rom).unwrap();
+            c8.reseed_from_rom();
+            (0..8)
+                .map(|_| {
+                    c8.execute_instruction(&Instruction::InsCXNN(0, 0xFF))
+                        .unwrap();
+                    c8.v_reg[0]
+                })
+                .collect()
+        }
+        let a = random_bytes(&[0x60, 0x01, 0x12, 0x02]);
+        let b = random_bytes(&[0x60, 0x02, 0x12, 0x02]);
+        assert_eq!(a, random_bytes(&[0x60, 0x01, 0x12, 0x02]));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_load_store_range() {
         let mut c8 = create_chip8();
-        // 测试 2NNN (CALL)
-        c8.pc = 0x200;
-        c8.memory[0x200] = 0x23;
-        c8.memory[0x201] = 0x00; // CALL 0x300
+        c8.v_reg = core::array::from_fn(|i| i as u8 + 1);
+        c8.i_reg = 0x300;
+        c8.execute_instruction(&Instruction::InsFX55(5)).unwrap();
+        assert_eq!(c8.memory[0x300..0x306], [1, 2, 3, 4, 5, 6]);
+        // V6-VF 对应的内存不受影响
+        assert!(c8.memory[0x306..0x310].iter().all(|&b| b == 0));
 
-        // 执行CALL
-        let opcode = c8.fetch_opcode();
-        let instruction = Instruction::try_from(opcode).unwrap();
-        c8.execute_instruction(&instruction);
+        // 越过内存末尾时报错而不是 panic
+        c8.i_reg = 0xFFE;
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::InsFX55(5)),
+            Err(EmuError::MemoryOutOfBounds(0xFFE))
+        ));
+        assert!(matches!(
+            c8.execute_instruction(&Instruction::InsFX65(5)),
+            Err(EmuError::MemoryOutOfBounds(0xFFE))
+        ));
+        c8.execute_instruction(&Instruction::InsFX65(1)).unwrap();
+        assert_eq!(c8.i_reg, 0x1000);
+    }
 
-        assert_eq!(c8.stack[0], 0x202); // 返回地址
-        assert_eq!(c8.stack_pointer, 1);
-        assert_eq!(c8.pc, 0x300);
+    #[test]
+    fn test_call_stack() {
+        let mut c8 = create_chip8();
+        c8.load_at(0x200, &[0x23, 0x00]).unwrap();
+        c8.load_at(0x300, &[0x24, 0x00]).unwrap();
+        assert!(c8.call_stack().is_empty());
+        c8.tick().unwrap();
+        c8.tick().unwrap();
+        assert_eq!(c8.call_stack(), &[0x202, 0x302]);
+        assert_eq!(c8.pc, 0x400);
+    }
 
-        // 测试 00EE (RET)
-        c8.memory[0x300] = 0x00;
-        c8.memory[0x301] = 0xEE;
-        c8.pc = 0x300;
+    #[test]
+    fn test_pc_out_of_bounds() {
+        let mut c8 = create_chip8();
+        c8.pc = 0xFFF;
+        assert!(matches!(c8.tick(), Err(EmuError::PcOutOfBounds(0xFFF))));
+        // 内存末尾的最后一条指令仍可执行, 之后 pc 越界
+        c8.memory[0xFFE] = 0x60;
+        c8.pc = 0xFFE;
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, 0x1000);
+        assert!(matches!(c8.tick(), Err(EmuError::PcOutOfBounds(0x1000))));
+    }
 
-        let opcode = c8.fetch_opcode();
-        let instruction = Instruction::try_from(opcode).unwrap();
-        c8.execute_instruction(&instruction);
+    #[test]
+    fn test_mock_rng() {
+        let mut c8: Chip8<_> = Chip8::new(MockRng { value: 0xFF });
+        c8.execute_instruction(&Instruction::InsCXNN(3, 0x0F))
+            .unwrap();
+        assert_eq!(c8.v_reg[3], 0x0F);
+    }
 
-        assert_eq!(c8.stack_pointer, 0);
+    #[test]
+    fn test_halt_on_unknown_opcode() {
+        let mut c8 = create_chip8();
+        c8.halt_on_unknown_opcode = true;
+        c8.load_program(&[0x6001, 0xF0FF, 0x6002]);
+        c8.tick().unwrap();
+        let result = c8.tick().unwrap();
+        assert!(result.halted);
+        assert!(c8.is_halted());
+        assert_eq!(c8.pc, 0x202);
+        assert_eq!(c8.current_opcode(), 0xF0FF);
+        assert_eq!(c8.unknown_opcode(), Some(0xF0FF));
+        assert_eq!(c8.halt_reason(), Some(HaltReason::UnknownOpcode(0xF0FF)));
+        // 停机后不再执行
+        assert!(c8.tick().unwrap().halted);
         assert_eq!(c8.pc, 0x202);
+
+        c8.reset();
+        assert_eq!(c8.unknown_opcode(), None);
     }
 
     #[test]
-    fn test_timer_decrement() {
+    fn test_font_region_intact() {
+        let mut c8 = create_chip8();
+        assert!(c8.font_region_intact());
+        c8.memory[10] ^= 0xFF;
+        assert!(!c8.font_region_intact());
+        c8.load_fontset();
+        assert!(c8.font_region_intact());
+    }
+
+    #[test]
+    fn test_reload_fontset() {
+        let mut c8 = create_chip8();
+        c8.memory[..FONTSET_SIZE].fill(0xEE);
+        c8.memory[0x300] = 0x12;
+        c8.reload_fontset();
+        assert_eq!(&c8.memory[..FONTSET_SIZE], &FONTSET[..]);
+        // 其余内存不受影响
+        assert_eq!(c8.memory[0x300], 0x12);
+    }
+
+    #[test]
+    fn test_recent_trace() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x6001, 0x6102, 0x6203, 0x6304]);
+        c8.tick().unwrap();
+        assert_eq!(c8.recent_trace().count(), 0); // 默认不记录
+
+        c8.trace_depth = 2;
+        for _ in 0..3 {
+            c8.tick().unwrap();
+        }
+        let trace: Vec<_> = c8.recent_trace().collect();
+        assert_eq!(trace, [(0x204, 0x6203), (0x206, 0x6304)]);
+    }
+
+    #[test]
+    fn test_tick_single_timer() {
         let mut c8 = create_chip8();
         c8.delay_timer = 5;
-        c8.sound_timer = 3;
+        c8.sound_timer = 5;
+        c8.tick_delay_timer();
+        assert_eq!(c8.timers(), (4, 5));
+        c8.tick_sound_timer();
+        c8.tick_sound_timer();
+        assert_eq!(c8.timers(), (4, 3));
 
-        c8.tick_timer();
-        assert_eq!(c8.delay_timer, 4);
-        assert_eq!(c8.sound_timer, 2);
+        c8.delay_timer = 0;
+        c8.tick_delay_timer();
+        assert_eq!(c8.delay_timer, 0);
+    }
+
+    #[test]
+    fn test_xo_chip_audio() {
+        let mut c8 = create_chip8();
+        let pattern: [u8; AUDIO_PATTERN_SIZE] = core::array::from_fn(|i| 0x11 * i as u8);
+        c8.load_at(0x300, &pattern).unwrap();
+        c8.i_reg = 0x300;
+        c8.v_reg[4] = 112;
+        // 未开启 XO-CHIP 时视为未知操作码
+        assert!(matches!(
+            c8.execute_opcode(0xF002),
+            Err(EmuError::Instruction(InstructionError::UnknownOpcode(
+                0xF002
+            )))
+        ));
+        assert!(matches!(
+            c8.execute_opcode(0xF43A),
+            Err(EmuError::Instruction(InstructionError::UnknownOpcode(
+                0xF43A
+            )))
+        ));
+        assert_eq!(c8.audio_pattern(), &[0; AUDIO_PATTERN_SIZE]);
+
+        // 解码阶段即被拒绝: halt_on_unknown_opcode 停机且 pc 指向该操作码
+        c8.load_program(&[0xF002]);
+        assert_eq!(c8.validate_rom(), [(0x200, 0xF002)]);
+        assert!(c8.reachable_from(0x200).is_empty());
+        c8.halt_on_unknown_opcode = true;
+        c8.tick().unwrap();
+        assert_eq!(c8.halt_reason(), Some(HaltReason::UnknownOpcode(0xF002)));
+        assert_eq!(c8.pc, 0x200);
+
+        c8.xo_chip = true;
+        c8.execute_opcode(0xF002).unwrap();
+        c8.execute_opcode(0xF43A).unwrap();
+        assert_eq!(c8.audio_pattern(), &pattern);
+        assert_eq!(c8.audio_pitch(), 112);
+        assert_eq!(c8.i_reg, 0x300);
+    }
+
+    #[test]
+    fn test_wrap_quirks() {
+        // 8x2 的实心精灵, 起点 (62, 31) 越过右边缘和下边缘
+        fn draw(wrap_x: bool, wrap_y: bool) -> Chip8<LinearCongruentialGenerator> {
+            let mut c8 = create_chip8();
+            c8.quirks.wrap_x = wrap_x;
+            c8.quirks.wrap_y = wrap_y;
+            c8.load_at(0x300, &[0xFF, 0xFF]).unwrap();
+            c8.i_reg = 0x300;
+            c8.v_reg[0] = 62;
+            c8.v_reg[1] = 31;
+            c8.execute_instruction(&Instruction::InsDXYN(0, 1, 2))
+                .unwrap();
+            c8
+        }
+        let count = |c8: &Chip8<LinearCongruentialGenerator>| {
+            c8.get_display().iter().filter(|&&p| p).count()
+        };
+
+        let c8 = draw(false, false);
+        assert_eq!(count(&c8), 2);
+        assert!(c8.screen.get(63, 31));
+
+        let c8 = draw(true, false);
+        assert_eq!(count(&c8), 8);
+        assert!(c8.screen.get(5, 31));
+        assert!(!c8.screen.get(62, 0));
+
+        let c8 = draw(false, true);
+        assert_eq!(count(&c8), 4);
+        assert!(c8.screen.get(62, 0));
+        assert!(!c8.screen.get(0, 31));
+
+        let c8 = draw(true, true);
+        assert_eq!(count(&c8), 16);
+        assert!(c8.screen.get(5, 0));
+    }
+
+    #[test]
+    fn test_undo_last_tick() {
+        let mut c8 = create_chip8();
+        c8.journal_depth = 16;
+        c8.load_program(&[0x6005, 0xA300, 0xF155]);
+        c8.v_reg[1] = 0x77;
+        c8.memory[0x300] = 0xAA;
+        c8.memory[0x301] = 0xBB;
+        for _ in 0..3 {
+            c8.tick().unwrap();
+        }
+        assert_eq!(&c8.memory[0x300..0x302], &[0x05, 0x77]);
+        assert_eq!(c8.i_reg, 0x302);
+
+        assert!(c8.undo_last_tick());
+        assert_eq!(&c8.memory[0x300..0x302], &[0xAA, 0xBB]);
+        assert_eq!(c8.i_reg, 0x300);
+        assert_eq!(c8.pc, 0x204);
+
+        assert!(c8.undo_last_tick());
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.v_reg[0], 0);
+        assert_eq!(c8.pc, PROGRAM_START);
+        assert!(!c8.undo_last_tick());
+    }
+
+    #[test]
+    fn test_undo_failed_and_extended_state() {
+        // 执行失败的指令不占用撤销记录
+        let mut c8 = create_chip8();
+        c8.journal_depth = 16;
+        c8.load_program(&[0x6005, 0xFFFF]);
+        c8.tick().unwrap();
+        assert!(c8.tick().is_err());
+        assert!(c8.undo_last_tick());
+        assert_eq!((c8.v_reg[0], c8.pc), (0, PROGRAM_START));
+        assert!(!c8.undo_last_tick());
+
+        // 2NNN 覆盖的调用栈槽位
+        let mut c8 = create_chip8();
+        c8.journal_depth = 16;
+        c8.load_program(&[0x2300]);
+        c8.stack[0] = 0xBEEF;
+        c8.tick().unwrap();
+        assert_eq!(c8.stack[0], 0x202);
+        assert!(c8.undo_last_tick());
+        assert_eq!((c8.stack[0], c8.stack_pointer), (0xBEEF, 0));
+
+        // XO-CHIP 平面选择和音频
+        let mut c8 = create_chip8();
+        c8.journal_depth = 16;
+        c8.xo_chip = true;
+        c8.load_program(&[0xF301, 0xA300, 0xF002, 0x6010, 0xF03A]);
+        c8.load_at(0x300, &[0x5A; AUDIO_PATTERN_SIZE]).unwrap();
+        for _ in 0..5 {
+            c8.tick().unwrap();
+        }
+        assert_eq!(c8.audio_pitch(), 0x10);
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.audio_pitch(), DEFAULT_AUDIO_PITCH);
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.audio_pattern(), &[0x5A; AUDIO_PATTERN_SIZE]);
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.audio_pattern(), &[0; AUDIO_PATTERN_SIZE]);
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.selected_planes, 0b11);
+        assert!(c8.undo_last_tick());
+        assert_eq!(c8.selected_planes, 0b01);
+    }
+
+    #[test]
+    fn test_journal_depth_and_clear() {
+        let mut c8 = create_chip8();
+        c8.journal_depth = 2;
+        c8.load_program(&[0x7001, 0x7001, 0x7001]);
+        for _ in 0..3 {
+            c8.tick().unwrap();
+        }
+        // 只保留最近两条
+        assert!(c8.undo_last_tick());
+        assert!(c8.undo_last_tick());
+        assert!(!c8.undo_last_tick());
+        assert_eq!(c8.v_reg[0], 1);
+
+        // 载入存档后不能撤销到另一个会话的状态
+        let saved = c8.save_state();
+        c8.tick().unwrap();
+        c8.load_state(&saved).unwrap();
+        assert!(!c8.undo_last_tick());
+        c8.tick().unwrap();
+        c8.reset();
+        assert!(!c8.undo_last_tick());
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x2300, 0x6A2A]);
+        c8.load_at(0x300, &[0xD0, 0x15]).unwrap();
+        c8.i_reg = 0x123;
+        c8.delay_timer = 7;
+        c8.keypress(0xB, true);
+        c8.tick().unwrap();
+        c8.tick().unwrap();
+        let state = c8.save_state();
+
+        // pc 紧跟在魔数, 版本号和内存之后, 按大端序存储
+        let offset = STATE_MAGIC.len() + 1 + MEMORY_SIZE;
+        assert_eq!(c8.pc, 0x302);
+        assert_eq!(&state[offset..offset + 2], &[0x03, 0x02]);
+
+        let mut restored = create_chip8();
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.pc, 0x302);
+        assert_eq!(restored.i_reg, 0x123);
+        assert_eq!(restored.delay_timer, 7);
+        assert_eq!(restored.call_stack(), &[0x202]);
+        assert!(restored.keypad[0xB]);
+        assert_eq!(restored.get_display(), c8.get_display());
+        assert_eq!(restored.rom_len, 4);
+        assert_eq!(restored.rom_checksum(), c8.rom_checksum());
+        assert_eq!(restored.save_state(), state);
+
+        // XO-CHIP 状态和停机原因
+        let mut c8 = create_chip8();
+        c8.xo_chip = true;
+        c8.load_program(&[0xF301, 0xA300, 0xF002, 0x6010, 0xF03A, 0xFFFF]);
+        c8.load_at(0x300, &[0x5A; AUDIO_PATTERN_SIZE]).unwrap();
+        c8.halt_on_unknown_opcode = true;
+        for _ in 0..5 {
+            c8.tick().unwrap();
+        }
+        c8.plane1.set(7, 3, true);
+        // FX0A 等待中的按键快照不会带到载入的存档
+        c8.keypress(0x1, true);
+        c8.execute_instruction(&Instruction::InsFX0A(0)).unwrap();
+        c8.pc = 0x20A;
+        assert!(c8.tick().unwrap().halted);
+        assert_eq!(c8.unknown_opcode(), Some(0xFFFF));
+        let state = c8.save_state();
+
+        let mut restored = create_chip8();
+        restored.keypress(0x1, true);
+        restored
+            .execute_instruction(&Instruction::InsFX0A(0))
+            .unwrap();
+        restored.load_state(&state).unwrap();
+        assert!(restored.key_wait_snapshot.is_none());
+        assert_eq!(restored.rom_len, 12);
+        assert_eq!(restored.selected_planes, 0b11);
+        assert!(restored.plane1.get(7, 3));
+        assert_eq!(restored.audio_pattern(), &[0x5A; AUDIO_PATTERN_SIZE]);
+        assert_eq!(restored.audio_pitch(), 0x10);
+        assert_eq!(
+            restored.halt_reason(),
+            Some(HaltReason::UnknownOpcode(0xFFFF))
+        );
+        assert_eq!(restored.save_state(), state);
+
+        let mut bad = state.clone();
+        let tag = bad.len() - 3;
+        bad[tag] = 9;
+        assert!(matches!(
+            restored.load_state(&bad),
+            Err(StateError::InvalidHaltReason(9))
+        ));
+
+        assert!(matches!(
+            restored.load_state(&state[..100]),
+            Err(StateError::Truncated)
+        ));
+        assert!(matches!(
+            restored.load_state(b"NOPE"),
+            Err(StateError::BadMagic)
+        ));
+        let mut hires: Chip8Super<_> = Chip8::new(LinearCongruentialGenerator::default());
+        assert!(matches!(
+            hires.load_state(&state),
+            Err(StateError::ResolutionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_fade_buffer() {
+        let mut c8 = create_chip8();
+        c8.screen.set(3, 2, true);
+        let idx = 3 + 2 * SCREEN_WIDTH;
+        assert_eq!(c8.fade_buffer()[idx], 255);
+        c8.age_display(40);
+
+        // 熄灭后逐帧变暗
+        c8.screen.set(3, 2, false);
+        c8.age_display(40);
+        c8.age_display(40);
+        let level = c8.fade_buffer()[idx];
+        assert_eq!(level, 255 - 80);
+        assert!(level > 0);
+        assert_eq!(c8.fade_buffer()[0], 0);
+
+        // 重新点亮立即恢复最大亮度
+        c8.screen.set(3, 2, true);
+        assert_eq!(c8.fade_buffer()[idx], 255);
+    }
+
+    #[test]
+    fn test_rom_bytes() {
+        let mut c8 = create_chip8();
+        assert!(c8.rom_bytes().is_empty());
+        c8.load_rom(&[0x60, 0x01, 0x12, 0x02]).unwrap();
+        assert_eq!(c8.rom_bytes(), &[0x60, 0x01, 0x12, 0x02]);
+        c8.reset();
+        assert!(c8.rom_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_keypress_char() {
+        let mut c8 = create_chip8();
+        assert!(c8.keypress_char('a', true));
+        assert!(c8.keypad[0xA]);
+        assert!(c8.keypress_char('7', true));
+        assert!(c8.keypad[0x7]);
+        assert!(c8.keypress_char('F', true));
+        assert!(c8.keypad[0xF]);
+        assert!(!c8.keypress_char('z', true));
+        assert_eq!(c8.keypad.iter().filter(|&&k| k).count(), 3);
+    }
+
+    #[test]
+    fn test_redundant_draws() {
+        let mut c8 = create_chip8();
+        c8.profiling = true;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        assert_eq!(c8.redundant_draws(), 0);
+        // 第二次在同一位置绘制同一精灵, 只是把它擦掉
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        assert_eq!(c8.redundant_draws(), 1);
+
+        c8.profiling = false;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 0))
+            .unwrap();
+        assert_eq!(c8.redundant_draws(), 1);
+    }
+
+    #[test]
+    fn test_freeze_register() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x6200, 0x7201, 0x6200]);
+        c8.freeze_register(2, 9);
+        c8.tick().unwrap();
+        assert_eq!(c8.v_reg[2], 9);
+        c8.tick().unwrap();
+        assert_eq!(c8.v_reg[2], 9);
+
+        c8.unfreeze_register(2);
+        c8.tick().unwrap();
+        assert_eq!(c8.v_reg[2], 0);
+    }
+
+    #[test]
+    fn test_hostile_state_never_panics() {
+        struct ForeverTime;
+
+        impl TimeSource for ForeverTime {
+            fn elapsed_ms(&mut self) -> u64 {
+                u64::MAX
+            }
+        }
+
+        let mut c8 = create_chip8();
+        c8.xo_chip = true;
+        c8.journal_depth = 4;
+        c8.trace_depth = 4;
+        c8.quirks.display_wait = true;
+        let stack_len = c8.stack.len() as u16;
+        // (pc, i, sp, 寄存器值)
+        let states = [
+            (0xFFFE, 0xFFFF, stack_len, 0xFF),
+            (0x0000, 0x0FFE, 0, 0xFF),
+            (0xFFFF, 0x0FF0, 200, 0x80),
+        ];
+        let mut errors = 0;
+        for (pc, i, sp, v) in states {
+            for opcode in 0..=u16::MAX {
+                c8.pc = pc;
+                c8.i_reg = i;
+                c8.stack_pointer = sp;
+                c8.v_reg = [v; NUM_REGISTERS];
+                c8.rom_len = usize::MAX;
+                errors += c8.execute_opcode(opcode).is_err() as u32;
+            }
+            c8.pc = pc;
+            errors += c8.tick().is_err() as u32;
+            c8.current_opcode();
+            let _ = c8.peek_instruction();
+            c8.validate_rom();
+            c8.rom_bytes();
+            c8.call_stack();
+        }
+        assert!(errors > 0);
+
+        c8.keypress(99, true);
+        assert!(!c8.keypress_edge(99, true));
+        c8.keypress_mapped(99, true);
+        c8.freeze_register(40, 1);
+        c8.unfreeze_register(40);
+        c8.update_timers(&mut ForeverTime);
+        c8.update_timers(&mut ForeverTime);
+        assert!(c8.load_rom(&[0; MEMORY_SIZE]).is_err());
+        c8.load_program(&[0x1200; MEMORY_SIZE]);
+        assert_eq!(c8.rom_bytes().len(), MEMORY_SIZE - ROM_START);
+        while c8.undo_last_tick() {}
+    }
+
+    #[test]
+    fn test_runaway_detection() {
+        let mut c8 = create_chip8();
+        c8.runaway_threshold = Some(100);
+        // 不绘制也不等待计时器的紧凑循环
+        c8.load_program(&[0x7001, 0x1200]);
+        for _ in 0..99 {
+            assert!(!c8.tick().unwrap().runaway);
+        }
+        assert!(c8.tick().unwrap().runaway);
+        assert!(c8.is_paused());
 
+        // 计时器更新会重新开始计数
+        c8.resume();
+        for _ in 0..50 {
+            c8.tick().unwrap();
+        }
         c8.tick_timer();
-        assert_eq!(c8.delay_timer, 3);
-        assert_eq!(c8.sound_timer, 1);
+        for _ in 0..99 {
+            assert!(!c8.tick().unwrap().runaway);
+        }
+
+        // 出错的指令不计数
+        c8.reset();
+        c8.runaway_threshold = Some(3);
+        c8.load_program(&[0xFFFF; 5]);
+        for _ in 0..5 {
+            assert!(c8.tick().is_err());
+        }
+        assert!(!c8.is_paused());
     }
 
     #[test]
-    fn test_draw_instruction() {
+    fn test_draw_image() {
         let mut c8 = create_chip8();
-        // 初始化精灵数据
-        c8.i_reg = 0x00;
-        c8.memory[0] = 0b11110000; // 4像素宽
+        let image = [0x80, 0x01, 0xFF, 0xFF];
+        assert!(!c8.draw_image(10, 4, &image, 2));
+        assert!(c8.draw_flag);
+        assert!(c8.screen.get(10, 4));
+        assert!(!c8.screen.get(11, 4));
+        assert!(c8.screen.get(25, 4)); // 第二个字节的最后一位
+        assert!((10..26).all(|x| c8.screen.get(x, 5)));
+        assert_eq!(c8.get_display().iter().filter(|&&p| p).count(), 18);
 
-        // 设置坐标
-        c8.v_reg[0] = 0; // V0 = X
-        c8.v_reg[1] = 0; // V1 = Y
+        // 再画一次全部擦除并报告碰撞
+        assert!(c8.draw_image(10, 4, &image, 2));
+        assert!(c8.get_display().iter().all(|&p| !p));
+    }
 
-        // 执行DXYN（D015）
-        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1));
+    #[test]
+    fn test_halt_reason() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x6001, 0x1202]);
+        c8.tick().unwrap();
+        assert_eq!(c8.halt_reason(), None);
+        c8.tick().unwrap();
+        assert_eq!(c8.halt_reason(), Some(HaltReason::InfiniteLoop));
 
-        // 验证第一行像素
-        assert!(c8.screen[0]); // 第1列
-        assert!(c8.screen[1]); // 第2列
-        assert!(c8.screen[2]); // 第3列
-        assert!(c8.screen[3]); // 第4列
-        assert_eq!(c8.v_reg[0xF], 0); // 无碰撞
+        // execute_opcode 不取指, 跳转到 pc - 2 并不是跳转到自身
+        c8.reset();
+        c8.pc = 0x300;
+        c8.execute_opcode(0x12FE).unwrap();
+        assert_eq!(c8.pc, 0x2FE);
+        assert_eq!(c8.halt_reason(), None);
+
+        c8.reset();
+        c8.load_program(&[0x00FD]);
+        c8.tick().unwrap();
+        assert_eq!(c8.halt_reason(), Some(HaltReason::Exit));
+
+        let mut c8: Chip8<_> = Chip8::with_stack_depth(LinearCongruentialGenerator::default(), 1);
+        c8.load_program(&[0x2200]);
+        c8.tick().unwrap();
+        assert!(c8.tick().is_err());
+        assert_eq!(c8.halt_reason(), Some(HaltReason::StackOverflow));
+        // 手动恢复后不再报告原因
+        c8.halted = false;
+        assert_eq!(c8.halt_reason(), None);
     }
 
     #[test]
-    fn test_arithmetic_instructions() {
+    fn test_xo_chip_plane_clear() {
         let mut c8 = create_chip8();
+        c8.xo_chip = true;
+        // 两个平面各用一行精灵数据
+        c8.load_at(0x300, &[0xF0, 0x0F]).unwrap();
+        c8.i_reg = 0x300;
+        c8.execute_opcode(0xF301).unwrap();
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 1))
+            .unwrap();
+        assert!(c8.screen.get(0, 0) && !c8.screen.get(4, 0));
+        assert!(c8.plane1.get(4, 0) && !c8.plane1.get(0, 0));
 
-        // 测试8XY4（ADD）
-        c8.v_reg[0] = 0xFE;
-        c8.v_reg[1] = 0x03;
-        c8.execute_instruction(&Instruction::Ins8XY4(0, 1));
-        assert_eq!(c8.v_reg[0], 0x01); // 溢出
-        assert_eq!(c8.v_reg[0xF], 1); // 进位标志
+        // 只选中平面 1 时 00E0 不影响平面 0
+        c8.execute_opcode(0xF201).unwrap();
+        c8.execute_instruction(&Instruction::Ins00E0).unwrap();
+        assert!(c8.screen.get(0, 0));
+        assert!(!c8.plane1.get(4, 0));
 
-        // 测试8XY5（SUB）
-        c8.v_reg[0] = 0x05;
-        c8.v_reg[1] = 0x03;
-        c8.execute_instruction(&Instruction::Ins8XY5(0, 1));
-        assert_eq!(c8.v_reg[0], 0x02);
-        assert_eq!(c8.v_reg[0xF], 1); // 无借位
+        // 经典模式下清除全部平面
+        c8.plane1.set(1, 1, true);
+        c8.xo_chip = false;
+        c8.execute_instruction(&Instruction::Ins00E0).unwrap();
+        assert!(!c8.screen.get(0, 0));
+        assert!(!c8.plane1.get(1, 1));
+        assert!(c8.execute_opcode(0xF101).is_err());
+        c8.load_program(&[0xF101]);
+        assert_eq!(c8.validate_rom(), [(0x200, 0xF101)]);
     }
 
     #[test]
-    fn test_keyboard_instructions() {
+    fn test_schip_collision_rows() {
+        let mut c8: Chip8Super<_> = Chip8::new(LinearCongruentialGenerator::default());
+        c8.quirks.schip_collision_rows = true;
+        c8.load_at(0x300, &[0xFF; 6]).unwrap();
+        c8.i_reg = 0x300;
+        c8.v_reg[0] = 10;
+        c8.v_reg[1] = 60; // 6 行中有 2 行越过底边
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 6))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 2);
+
+        // 再画一次: 4 行碰撞 + 2 行被裁剪
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 6))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 6);
+
+        c8.quirks.schip_collision_rows = false;
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 6))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 0);
+
+        // 选中两个平面时被裁剪的行只计一次
+        c8.clear_display();
+        c8.quirks.schip_collision_rows = true;
+        c8.xo_chip = true;
+        c8.selected_planes = 0b11;
+        c8.load_at(0x300, &[0xFF; 12]).unwrap();
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 6))
+            .unwrap();
+        assert_eq!(c8.v_reg[0xF], 2);
+    }
+
+    #[test]
+    fn test_render_ascii() {
         let mut c8 = create_chip8();
+        c8.v_reg[0] = 2;
+        c8.v_reg[1] = 1;
+        c8.i_reg = 0; // 字符 "0": F0 90 90 90 F0
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        let ascii = c8.render_ascii();
+        let rows: Vec<&str> = ascii.lines().collect();
+        assert_eq!(rows.len(), SCREEN_HEIGHT);
+        assert!(rows.iter().all(|row| row.chars().count() == SCREEN_WIDTH));
+        assert_eq!(rows[0].trim_end(), "");
+        assert_eq!(rows[1].trim_end(), "  ████");
+        assert_eq!(rows[2].trim_end(), "  █  █");
+        assert_eq!(rows[5].trim_end(), "  ████");
+    }
 
-        // 测试EXA1（SKNP）
-        c8.v_reg[0] = 0xA; // 检查按键A（hex key）
-        c8.keypad[0xA] = false;
-        c8.execute_instruction(&Instruction::InsEXA1(0));
-        assert_eq!(c8.pc, 0x200 + 2); // 应该跳过
+    #[test]
+    fn test_subtract_borrow_quirk() {
+        let sub = |inverted: bool, vx: u8, vy: u8| {
+            let mut c8 = create_chip8();
+            c8.quirks.subtract_borrow_inverted = inverted;
+            c8.v_reg[0] = vx;
+            c8.v_reg[1] = vy;
+            c8.execute_instruction(&Instruction::Ins8XY5(0, 1)).unwrap();
+            let vf_xy5 = c8.v_reg[0xF];
+            c8.v_reg[0] = vx;
+            c8.execute_instruction(&Instruction::Ins8XY7(0, 1)).unwrap();
+            (vf_xy5, c8.v_reg[0xF])
+        };
+        // VX < VY: 8XY5 借位, 8XY7 不借位
+        assert_eq!(sub(false, 1, 5), (0, 1));
+        assert_eq!(sub(true, 1, 5), (1, 0));
+        // VX > VY
+        assert_eq!(sub(false, 5, 1), (1, 0));
+        assert_eq!(sub(true, 5, 1), (0, 1));
+    }
 
-        // 测试FX0A（等待按键）
-        c8.keypad[0x5] = true;
-        c8.execute_instruction(&Instruction::InsFX0A(0));
-        assert_eq!(c8.v_reg[0], 0x5);
+    #[test]
+    fn test_bitset_draw_matches_per_pixel() {
+        // 逐像素的参考实现, 与按位存储之前的 DXYN 行为一致
+        fn reference(
+            pixels: &mut [[bool; SCREEN_WIDTH]; SCREEN_HEIGHT],
+            x: usize,
+            y: usize,
+            sprite: &[u8],
+            wrap: bool,
+        ) -> bool {
+            let mut collision = false;
+            for (row, &byte) in sprite.iter().enumerate() {
+                let mut py = y % SCREEN_HEIGHT + row;
+                if py >= SCREEN_HEIGHT {
+                    if !wrap {
+                        break;
+                    }
+                    py %= SCREEN_HEIGHT;
+                }
+                for col in 0..8 {
+                    let mut px = x % SCREEN_WIDTH + col;
+                    if px >= SCREEN_WIDTH {
+                        if !wrap {
+                            break;
+                        }
+                        px %= SCREEN_WIDTH;
+                    }
+                    if byte & (0x80 >> col) != 0 {
+                        collision |= pixels[py][px];
+                        pixels[py][px] ^= true;
+                    }
+                }
+            }
+            collision
+        }
+
+        let sprites: [&[u8]; 4] = [
+            &[0xF0, 0x90, 0x90, 0x90, 0xF0],
+            &[0xFF; 15],
+            &[0x81, 0x42, 0x24, 0x18],
+            &[0x3C, 0x7E, 0xDB, 0xFF],
+        ];
+        let positions = [
+            (0, 0),
+            (3, 5),
+            (60, 10),
+            (62, 29),
+            (57, 31),
+            (70, 40),
+            (8, 8),
+        ];
+        for wrap in [false, true] {
+            let mut c8 = create_chip8();
+            c8.quirks.wrap_x = wrap;
+            c8.quirks.wrap_y = wrap;
+            let mut expected = [[false; SCREEN_WIDTH]; SCREEN_HEIGHT];
+            for sprite in sprites {
+                c8.memory[0x300..0x300 + sprite.len()].copy_from_slice(sprite);
+                c8.i_reg = 0x300;
+                for &(x, y) in &positions {
+                    c8.v_reg[0] = x as u8;
+                    c8.v_reg[1] = y as u8;
+                    c8.execute_instruction(&Instruction::InsDXYN(0, 1, sprite.len() as u8))
+                        .unwrap();
+                    let collision = reference(&mut expected, x, y, sprite, wrap);
+                    assert_eq!(c8.v_reg[0xF], collision as u8, "({x}, {y}) wrap={wrap}");
+                    assert_eq!(c8.get_display(), expected.as_flattened());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aligned_draw_fast_path() {
+        let sprite = [0xF0, 0x90, 0xFF, 0x81, 0x3C];
+        let mut c8 = create_chip8();
+        c8.memory[0x300..0x305].copy_from_slice(&sprite);
+        c8.i_reg = 0x300;
+        c8.v_reg[0] = 8;
+        c8.v_reg[1] = 2;
+        // 慢速路径: 逐像素异或
+        let mut slow: Screen = Screen::default();
+        slow.set(11, 3, true);
+        c8.screen.set(11, 3, true);
+        let mut collision = false;
+        for (row, byte) in sprite.iter().enumerate() {
+            for col in 0..8 {
+                collision |= slow.xor(8 + col, 2 + row, byte & (0x80 >> col) != 0);
+            }
+        }
+        c8.execute_instruction(&Instruction::InsDXYN(0, 1, 5))
+            .unwrap();
+        assert!(collision);
+        assert_eq!(c8.v_reg[0xF], 1);
+        assert!(c8.screen.pixels().eq(slow.pixels()));
+        assert_eq!(c8.screen.take_dirty_rect(), slow.take_dirty_rect());
+    }
+
+    #[test]
+    fn test_reachable_from() {
+        let mut c8 = create_chip8();
+        c8.load_rom(&[
+            0x22, 0x08, // 0x200: CALL 0x208
+            0x30, 0x00, // 0x202: SE V0, 0
+            0x12, 0x02, // 0x204: JP 0x202
+            0x12, 0x06, // 0x206: JP 0x206
+            0xA2, 0x0E, // 0x208: LD I, 0x20E
+            0x00, 0xEE, // 0x20A: RET
+            0xFF, 0xFF, // 0x20C: 未引用
+            0xF0, 0x90, 0xF0, 0x90, // 0x20E: 数据表
+        ])
+        .unwrap();
+        let reachable = c8.reachable_from(0x200);
+        assert_eq!(
+            reachable.into_iter().collect::<Vec<_>>(),
+            [0x200, 0x202, 0x204, 0x206, 0x208, 0x20A]
+        );
+        assert!((0x20C..0x212).all(|addr| !c8.reachable_from(0x200).contains(&addr)));
+    }
+
+    #[test]
+    fn test_current_tone() {
+        let mut c8 = create_chip8();
+        assert_eq!(c8.current_tone(), Tone::Silent);
+        c8.sound_timer = 3;
+        assert_eq!(
+            c8.current_tone(),
+            Tone::Square {
+                hz: DEFAULT_TONE_HZ
+            }
+        );
+        c8.tone_hz = 880.0;
+        assert_eq!(c8.current_tone(), Tone::Square { hz: 880.0 });
+
+        c8.xo_chip = true;
+        assert_eq!(
+            c8.current_tone(),
+            Tone::Pattern {
+                bytes: [0; AUDIO_PATTERN_SIZE],
+                pitch: DEFAULT_AUDIO_PITCH
+            }
+        );
+        c8.sound_timer = 0;
+        assert_eq!(c8.current_tone(), Tone::Silent);
+    }
+
+    #[test]
+    fn test_strict_decode() {
+        let mut c8 = create_chip8();
+        c8.load_program(&[0x5AB1, 0x6001]);
+        assert!(matches!(
+            c8.tick(),
+            Err(EmuError::Instruction(InstructionError::UnknownOpcode(
+                0x5AB1
+            )))
+        ));
+
+        // 宽松模式下按 5AB0 执行: VA == VB, 跳过下一条指令
+        c8.reset();
+        c8.strict_decode = false;
+        c8.load_program(&[0x5AB1, 0x6001, 0x6002]);
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, 0x204);
+        c8.tick().unwrap();
+        assert_eq!(c8.v_reg[0], 2);
+
+        // 其余未知尾数保留原始操作码, 单独统计
+        c8.reset();
+        c8.profiling = true;
+        c8.load_program(&[0x812F]);
+        assert!(matches!(
+            c8.peek_instruction(),
+            Ok(Instruction::InsUnknown(0x812F))
+        ));
+        c8.tick().unwrap();
+        assert_eq!(c8.pc, 0x202);
+        assert_eq!(c8.opcode_histogram().get("????"), Some(&1));
+        assert_eq!(c8.opcode_histogram().get("7XNN"), None);
+    }
+
+    #[test]
+    fn test_instruction_at() {
+        let mut c8 = create_chip8();
+        c8.memory[0x300] = 0xD1;
+        c8.memory[0x301] = 0x25;
+        c8.memory[0x302] = 0x5A;
+        c8.memory[0x303] = 0xB1;
+        assert!(matches!(
+            c8.instruction_at(0x300),
+            Ok(Instruction::InsDXYN(1, 2, 5))
+        ));
+        assert!(matches!(
+            c8.instruction_at(0x302),
+            Err(InstructionError::UnknownOpcode(0x5AB1))
+        ));
+        assert_eq!(c8.pc, PROGRAM_START);
     }
 }
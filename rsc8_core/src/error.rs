@@ -1,3 +1,4 @@
+// 操作码解码失败
 pub enum InstructionError {
     UnknownOpcode(u16),
 }
@@ -17,3 +18,204 @@ impl core::fmt::Display for InstructionError {
 }
 
 impl core::error::Error for InstructionError {}
+
+// 执行期间的错误, tick/step 等返回此类型
+pub enum EmuError {
+    Instruction(InstructionError),
+    StackOverflow,
+    StackUnderflow,
+    BadReturn(u16),         // 00EE 时 stack_pointer 超出调用栈, 记录该值
+    MemoryOutOfBounds(u16), // 访问超出 MEMORY_SIZE, 记录起始地址
+    ProtectedWrite(u16),    // 写入了受保护的解释器区域
+    PcOutOfBounds(u16),     // pc 处无法取出完整的操作码
+}
+
+impl core::fmt::Debug for EmuError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EmuError::Instruction(err) => write!(f, "Instruction({:?})", err),
+            EmuError::StackOverflow => write!(f, "StackOverflow"),
+            EmuError::StackUnderflow => write!(f, "StackUnderflow"),
+            EmuError::BadReturn(sp) => write!(f, "BadReturn({})", sp),
+            EmuError::MemoryOutOfBounds(addr) => write!(f, "MemoryOutOfBounds({:04x})", addr),
+            EmuError::ProtectedWrite(addr) => write!(f, "ProtectedWrite({:04x})", addr),
+            EmuError::PcOutOfBounds(pc) => write!(f, "PcOutOfBounds({:04x})", pc),
+        }
+    }
+}
+
+impl core::fmt::Display for EmuError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl core::error::Error for EmuError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            EmuError::Instruction(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InstructionError> for EmuError {
+    fn from(err: InstructionError) -> Self {
+        EmuError::Instruction(err)
+    }
+}
+
+pub enum LoadError {
+    OutOfBounds { addr: u16, len: usize },
+    FontRegion(u16),
+    InvalidHex(usize), // 出错的字符位置(忽略空白后)
+}
+
+impl core::fmt::Debug for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::OutOfBounds { addr, len } => {
+                write!(f, "OutOfBounds({:04x}, {})", addr, len)
+            }
+            LoadError::FontRegion(addr) => write!(f, "FontRegion({:04x})", addr),
+            LoadError::InvalidHex(pos) => write!(f, "InvalidHex({})", pos),
+        }
+    }
+}
+
+impl core::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+// Quirks 配置解析错误, 均记录出错的行号(从 1 开始)
+pub enum ParseQuirkError {
+    UnknownKey(usize),
+    InvalidValue(usize), // 值不是 true/false
+    MissingValue(usize), // 缺少 '='
+}
+
+impl core::fmt::Debug for ParseQuirkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseQuirkError::UnknownKey(line) => write!(f, "UnknownKey({})", line),
+            ParseQuirkError::InvalidValue(line) => write!(f, "InvalidValue({})", line),
+            ParseQuirkError::MissingValue(line) => write!(f, "MissingValue({})", line),
+        }
+    }
+}
+
+impl core::fmt::Display for ParseQuirkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl core::error::Error for ParseQuirkError {}
+
+// load_state 失败的原因
+pub enum StateError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidStackPointer(u16),
+    ResolutionMismatch, // 存档的屏幕尺寸与当前类型不一致
+    InvalidHaltReason(u8),
+}
+
+impl core::fmt::Debug for StateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "BadMagic"),
+            StateError::UnsupportedVersion(v) => write!(f, "UnsupportedVersion({})", v),
+            StateError::Truncated => write!(f, "Truncated"),
+            StateError::InvalidStackPointer(sp) => write!(f, "InvalidStackPointer({})", sp),
+            StateError::ResolutionMismatch => write!(f, "ResolutionMismatch"),
+            StateError::InvalidHaltReason(tag) => write!(f, "InvalidHaltReason({})", tag),
+        }
+    }
+}
+
+impl core::fmt::Display for StateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl core::error::Error for StateError {}
+
+// play_session 失败的原因
+pub enum SessionError {
+    Load(LoadError),
+    Emu(EmuError),
+    Paused(u64), // 回放中途被暂停, 记录当时的 cycle_count
+}
+
+impl core::fmt::Debug for SessionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SessionError::Load(err) => write!(f, "Load({:?})", err),
+            SessionError::Emu(err) => write!(f, "Emu({:?})", err),
+            SessionError::Paused(cycle) => write!(f, "Paused({})", cycle),
+        }
+    }
+}
+
+impl core::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl core::error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            SessionError::Load(err) => Some(err),
+            SessionError::Emu(err) => Some(err),
+            SessionError::Paused(_) => None,
+        }
+    }
+}
+
+impl From<LoadError> for SessionError {
+    fn from(err: LoadError) -> Self {
+        SessionError::Load(err)
+    }
+}
+
+impl From<EmuError> for SessionError {
+    fn from(err: EmuError) -> Self {
+        SessionError::Emu(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_emu_error_display() {
+        let cases = [
+            (
+                EmuError::Instruction(InstructionError::UnknownOpcode(0xF0FF)),
+                "Instruction(UnknownOpcode(f0ff))",
+            ),
+            (EmuError::StackOverflow, "StackOverflow"),
+            (EmuError::StackUnderflow, "StackUnderflow"),
+            (EmuError::BadReturn(20), "BadReturn(20)"),
+            (
+                EmuError::MemoryOutOfBounds(0xFFE),
+                "MemoryOutOfBounds(0ffe)",
+            ),
+            (EmuError::ProtectedWrite(0x100), "ProtectedWrite(0100)"),
+            (EmuError::PcOutOfBounds(0xFFF), "PcOutOfBounds(0fff)"),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+}
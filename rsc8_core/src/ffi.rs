@@ -0,0 +1,190 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    chip8::{Chip8, KEYPAD_SIZE, SCREEN_HEIGHT, SCREEN_WIDTH},
+    rng::LinearCongruentialGenerator,
+};
+
+// 非泛型的包装, 方便通过 FFI / WASM 边界调用
+pub struct EmuHandle {
+    chip8: Chip8<LinearCongruentialGenerator>,
+    display: Vec<u8>, // display_ptr 返回的缓冲区, 屏幕本身按位存储
+}
+
+impl Default for EmuHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmuHandle {
+    pub fn new() -> Self {
+        let mut chip8 = Chip8::new(LinearCongruentialGenerator::default());
+        chip8.load_fontset();
+        Self {
+            chip8,
+            display: Vec::new(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` 必须指向 `len` 个可读字节
+    pub unsafe fn load_rom(&mut self, ptr: *const u8, len: usize) -> bool {
+        if ptr.is_null() {
+            return false;
+        }
+        let rom = core::slice::from_raw_parts(ptr, len);
+        self.chip8.load_rom(rom).is_ok()
+    }
+
+    // 出错时返回 false
+    pub fn tick(&mut self) -> bool {
+        self.chip8.tick().is_ok()
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.chip8.tick_timer();
+    }
+
+    pub fn key(&mut self, idx: usize, down: bool) {
+        if idx < KEYPAD_SIZE {
+            self.chip8.keypress(idx, down);
+        }
+    }
+
+    // 指向 display_len 个字节的屏幕缓冲区, 每个像素一个字节, 0 或 1
+    // 每次调用时刷新, 指针在下次调用 display_ptr 前有效
+    pub fn display_ptr(&mut self) -> *const u8 {
+        self.display.clear();
+        self.display
+            .extend(self.chip8.screen.pixels().map(|on| on as u8));
+        self.display.as_ptr()
+    }
+
+    pub fn display_len(&self) -> usize {
+        SCREEN_WIDTH * SCREEN_HEIGHT
+    }
+}
+
+// 以下是 C ABI 的入口, 宿主(JS 或 C)只持有 emu_new 返回的不透明指针
+// 传入空指针时什么也不做, 返回 false 或空指针
+
+#[no_mangle]
+pub extern "C" fn emu_new() -> *mut EmuHandle {
+    Box::into_raw(Box::new(EmuHandle::new()))
+}
+
+/// # Safety
+///
+/// `emu` 必须是 emu_new 返回且尚未释放的指针, 或空指针
+#[no_mangle]
+pub unsafe extern "C" fn emu_free(emu: *mut EmuHandle) {
+    if !emu.is_null() {
+        drop(Box::from_raw(emu));
+    }
+}
+
+/// # Safety
+///
+/// `emu` 同 emu_free; `ptr` 必须指向 `len` 个可读字节
+#[no_mangle]
+pub unsafe extern "C" fn emu_load_rom(emu: *mut EmuHandle, ptr: *const u8, len: usize) -> bool {
+    match emu.as_mut() {
+        Some(emu) => emu.load_rom(ptr, len),
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `emu` 同 emu_free
+#[no_mangle]
+pub unsafe extern "C" fn emu_tick(emu: *mut EmuHandle) -> bool {
+    emu.as_mut().is_some_and(EmuHandle::tick)
+}
+
+/// # Safety
+///
+/// `emu` 同 emu_free
+#[no_mangle]
+pub unsafe extern "C" fn emu_tick_timers(emu: *mut EmuHandle) {
+    if let Some(emu) = emu.as_mut() {
+        emu.tick_timers();
+    }
+}
+
+/// # Safety
+///
+/// `emu` 同 emu_free
+#[no_mangle]
+pub unsafe extern "C" fn emu_key(emu: *mut EmuHandle, idx: usize, down: bool) {
+    if let Some(emu) = emu.as_mut() {
+        emu.key(idx, down);
+    }
+}
+
+/// # Safety
+///
+/// `emu` 同 emu_free; 返回的指针在下次调用 emu_display_ptr 或 emu_free 前有效
+#[no_mangle]
+pub unsafe extern "C" fn emu_display_ptr(emu: *mut EmuHandle) -> *const u8 {
+    match emu.as_mut() {
+        Some(emu) => emu.display_ptr(),
+        None => core::ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn emu_display_len() -> usize {
+    SCREEN_WIDTH * SCREEN_HEIGHT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emu_handle() {
+        let mut emu = EmuHandle::new();
+        // LD V0, 0; LD V1, 0; LD F, V0; DRW V0, V1, 5
+        let rom = [0x60, 0x00, 0x61, 0x00, 0xF0, 0x29, 0xD0, 0x15];
+        assert!(unsafe { emu.load_rom(rom.as_ptr(), rom.len()) });
+        for _ in 0..4 {
+            assert!(emu.tick());
+        }
+
+        assert_eq!(emu.display_len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        let display = unsafe { core::slice::from_raw_parts(emu.display_ptr(), emu.display_len()) };
+        // 字符 0 的第一行 0xF0
+        assert_eq!(&display[..5], &[1, 1, 1, 1, 0]);
+        assert_eq!(display[SCREEN_WIDTH], 1);
+        assert_eq!(display[SCREEN_WIDTH + 1], 0);
+    }
+
+    #[test]
+    fn test_extern_api() {
+        let rom = [0x60, 0x00, 0x61, 0x00, 0xF0, 0x29, 0xD0, 0x15];
+        unsafe {
+            let emu = emu_new();
+            assert!(emu_load_rom(emu, rom.as_ptr(), rom.len()));
+            for _ in 0..4 {
+                assert!(emu_tick(emu));
+            }
+            emu_key(emu, 0x3, true);
+            assert!((*emu).chip8.keypad[0x3]);
+            emu_tick_timers(emu);
+            let display = core::slice::from_raw_parts(emu_display_ptr(emu), emu_display_len());
+            assert_eq!(&display[..5], &[1, 1, 1, 1, 0]);
+            emu_free(emu);
+
+            // 空指针被忽略
+            let null = core::ptr::null_mut();
+            assert!(!emu_load_rom(null, rom.as_ptr(), rom.len()));
+            assert!(!emu_tick(null));
+            emu_key(null, 0, true);
+            assert!(emu_display_ptr(null).is_null());
+            emu_free(null);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub cycle: u64,
+    pub key: usize,
+    pub pressed: bool,
+}
+
+// 按指令周期排序的按键脚本, 用于确定性回放
+#[derive(Clone, Debug, Default)]
+pub struct KeyScript {
+    events: Vec<KeyEvent>,
+    next: usize, // 下一个未应用的事件
+}
+
+impl KeyScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 同一周期的事件按加入顺序应用
+    pub fn push(&mut self, cycle: u64, key: usize, pressed: bool) {
+        let idx = self.next + self.events[self.next..].partition_point(|e| e.cycle <= cycle);
+        self.events.insert(
+            idx,
+            KeyEvent {
+                cycle,
+                key,
+                pressed,
+            },
+        );
+    }
+
+    pub fn pending(&self) -> &[KeyEvent] {
+        &self.events[self.next..]
+    }
+
+    pub fn rewind(&mut self) {
+        self.next = 0;
+    }
+
+    // 取出所有到期(cycle 不晚于给定周期)的事件
+    pub fn take_due(&mut self, cycle: u64) -> &[KeyEvent] {
+        let start = self.next;
+        self.next += self.events[start..].partition_point(|e| e.cycle <= cycle);
+        &self.events[start..self.next]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_due() {
+        let mut script = KeyScript::new();
+        script.push(5, 0x1, true);
+        script.push(2, 0x2, true);
+        script.push(5, 0x1, false);
+
+        assert!(script.take_due(1).is_empty());
+        assert_eq!(
+            script.take_due(4),
+            [KeyEvent {
+                cycle: 2,
+                key: 0x2,
+                pressed: true
+            }]
+        );
+        let due = script.take_due(5);
+        assert_eq!(due.len(), 2);
+        assert!(due[0].pressed);
+        assert!(!due[1].pressed);
+        assert!(script.pending().is_empty());
+
+        script.rewind();
+        assert_eq!(script.pending().len(), 3);
+    }
+}
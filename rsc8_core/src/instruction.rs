@@ -1,8 +1,11 @@
 use crate::error::InstructionError;
 
+#[derive(Debug)]
 pub enum Instruction {
     Ins00E0,             // 清屏
     Ins00EE,             // 返回
+    Ins0NNN(u16),        // 调用机器码子程序, 通常忽略
+    Ins00FD,             // SCHIP 退出解释器
     Ins1NNN(u16),        // 跳转到addr NNN
     Ins2NNN(u16),        // 调用子程序
     Ins3XNN(u8, u8),     // Skip if(VX == 0xNN)
@@ -35,9 +38,132 @@ pub enum Instruction {
     InsFX33(u8),         // Stores BCD encoding of VX into I
     InsFX55(u8),         // Stores V0 thru VX into RAM address starting at I
     InsFX65(u8),         // Fills V0 thru VX with RAM values starting at address in I
+    InsF002,             // XO-CHIP 从 I 处载入 16 字节音频样本
+    InsFX3A(u8),         // XO-CHIP 音高 = VX
+    InsFN01(u8),         // XO-CHIP 选择绘图平面 (位掩码)
+    InsUnknown(u16),     // 宽松解码时无法识别的原始操作码, 按空操作执行
 }
 
+// 指令分类, 用于界面着色和统计
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionKind {
+    FlowControl, // 跳转, 调用, 条件跳过
+    Arithmetic,
+    Logic, // 位运算与移位
+    Memory,
+    Display,
+    Input,
+    Timer,
+    Sound, // XO-CHIP 音频样本与音高
+    Misc,  // 寄存器赋值, 随机数
+}
+
+pub const INSTRUCTION_COUNT: usize = 40;
+
+// 与 Instruction::id 一一对应
+pub(crate) const INSTRUCTION_NAMES: [&str; INSTRUCTION_COUNT] = [
+    "00E0", "00EE", "1NNN", "2NNN", "3XNN", "4XNN", "5XY0", "6XNN", "7XNN", "8XY0", "8XY1", "8XY2",
+    "8XY3", "8XY4", "8XY5", "8XY6", "8XY7", "8XYE", "9XY0", "ANNN", "BNNN", "CXNN", "DXYN", "EX9E",
+    "EXA1", "FX07", "FX0A", "FX15", "FX18", "FX1E", "FX29", "FX33", "FX55", "FX65", "0NNN", "00FD",
+    "F002", "FX3A", "FN01", "????",
+];
+
 impl Instruction {
+    // 变体序号, 用于按指令统计
+    pub(crate) fn id(&self) -> usize {
+        match self {
+            Instruction::Ins00E0 => 0,
+            Instruction::Ins00EE => 1,
+            Instruction::Ins1NNN(_) => 2,
+            Instruction::Ins2NNN(_) => 3,
+            Instruction::Ins3XNN(..) => 4,
+            Instruction::Ins4XNN(..) => 5,
+            Instruction::Ins5XY0(..) => 6,
+            Instruction::Ins6XNN(..) => 7,
+            Instruction::Ins7XNN(..) => 8,
+            Instruction::Ins8XY0(..) => 9,
+            Instruction::Ins8XY1(..) => 10,
+            Instruction::Ins8XY2(..) => 11,
+            Instruction::Ins8XY3(..) => 12,
+            Instruction::Ins8XY4(..) => 13,
+            Instruction::Ins8XY5(..) => 14,
+            Instruction::Ins8XY6(..) => 15,
+            Instruction::Ins8XY7(..) => 16,
+            Instruction::Ins8XYE(..) => 17,
+            Instruction::Ins9XY0(..) => 18,
+            Instruction::InsANNN(_) => 19,
+            Instruction::InsBNNN(_) => 20,
+            Instruction::InsCXNN(..) => 21,
+            Instruction::InsDXYN(..) => 22,
+            Instruction::InsEX9E(_) => 23,
+            Instruction::InsEXA1(_) => 24,
+            Instruction::InsFX07(_) => 25,
+            Instruction::InsFX0A(_) => 26,
+            Instruction::InsFX15(_) => 27,
+            Instruction::InsFX18(_) => 28,
+            Instruction::InsFX1E(_) => 29,
+            Instruction::InsFX29(_) => 30,
+            Instruction::InsFX33(_) => 31,
+            Instruction::InsFX55(_) => 32,
+            Instruction::InsFX65(_) => 33,
+            Instruction::Ins0NNN(_) => 34,
+            Instruction::Ins00FD => 35,
+            Instruction::InsF002 => 36,
+            Instruction::InsFX3A(_) => 37,
+            Instruction::InsFN01(_) => 38,
+            Instruction::InsUnknown(_) => 39,
+        }
+    }
+
+    // 规范的操作码模式, 例如 "8XY4", 不含操作数
+    pub fn opcode_pattern(&self) -> &'static str {
+        INSTRUCTION_NAMES[self.id()]
+    }
+
+    pub fn kind(&self) -> InstructionKind {
+        match self {
+            Instruction::Ins00EE
+            | Instruction::Ins00FD
+            | Instruction::Ins1NNN(_)
+            | Instruction::Ins2NNN(_)
+            | Instruction::Ins3XNN(..)
+            | Instruction::Ins4XNN(..)
+            | Instruction::Ins5XY0(..)
+            | Instruction::Ins9XY0(..)
+            | Instruction::InsBNNN(_) => InstructionKind::FlowControl,
+            Instruction::Ins7XNN(..)
+            | Instruction::Ins8XY4(..)
+            | Instruction::Ins8XY5(..)
+            | Instruction::Ins8XY7(..) => InstructionKind::Arithmetic,
+            Instruction::Ins8XY1(..)
+            | Instruction::Ins8XY2(..)
+            | Instruction::Ins8XY3(..)
+            | Instruction::Ins8XY6(..)
+            | Instruction::Ins8XYE(..) => InstructionKind::Logic,
+            Instruction::InsANNN(_)
+            | Instruction::InsFX1E(_)
+            | Instruction::InsFX29(_)
+            | Instruction::InsFX33(_)
+            | Instruction::InsFX55(_)
+            | Instruction::InsFX65(_) => InstructionKind::Memory,
+            Instruction::Ins00E0 | Instruction::InsDXYN(..) | Instruction::InsFN01(_) => {
+                InstructionKind::Display
+            }
+            Instruction::InsEX9E(_) | Instruction::InsEXA1(_) | Instruction::InsFX0A(_) => {
+                InstructionKind::Input
+            }
+            Instruction::InsFX07(_) | Instruction::InsFX15(_) | Instruction::InsFX18(_) => {
+                InstructionKind::Timer
+            }
+            Instruction::InsF002 | Instruction::InsFX3A(_) => InstructionKind::Sound,
+            Instruction::Ins0NNN(_)
+            | Instruction::Ins6XNN(..)
+            | Instruction::Ins8XY0(..)
+            | Instruction::InsCXNN(..)
+            | Instruction::InsUnknown(_) => InstructionKind::Misc,
+        }
+    }
+
     // 分解为四个 4 位(半字节)的部分
     fn nibbles(opcode: u16) -> (u8, u8, u8, u8) {
         (
@@ -49,14 +175,218 @@ impl Instruction {
     }
 
     // addr
-    fn nnn(opcode: u16) -> u16 {
+    fn nnn_of(opcode: u16) -> u16 {
         opcode & 0x0FFF
     }
 
     // immediate value
-    fn nn(opcode: u16) -> u8 {
+    fn nn_of(opcode: u16) -> u8 {
         (opcode & 0x00FF) as u8
     }
+
+    // 重新编码为规范的操作码, 与 TryFrom<u16> 互逆
+    pub fn to_opcode(&self) -> u16 {
+        let xy = |n1: u16, x: u8, y: u8, n4: u16| n1 << 12 | (x as u16) << 8 | (y as u16) << 4 | n4;
+        let xnn = |n1: u16, x: u8, nn: u8| n1 << 12 | (x as u16) << 8 | nn as u16;
+        match *self {
+            Instruction::Ins00E0 => 0x00E0,
+            Instruction::Ins00EE => 0x00EE,
+            Instruction::Ins0NNN(nnn) => nnn & 0x0FFF,
+            Instruction::Ins00FD => 0x00FD,
+            Instruction::Ins1NNN(nnn) => 0x1000 | nnn & 0x0FFF,
+            Instruction::Ins2NNN(nnn) => 0x2000 | nnn & 0x0FFF,
+            Instruction::Ins3XNN(x, nn) => xnn(0x3, x, nn),
+            Instruction::Ins4XNN(x, nn) => xnn(0x4, x, nn),
+            Instruction::Ins5XY0(x, y) => xy(0x5, x, y, 0x0),
+            Instruction::Ins6XNN(x, nn) => xnn(0x6, x, nn),
+            Instruction::Ins7XNN(x, nn) => xnn(0x7, x, nn),
+            Instruction::Ins8XY0(x, y) => xy(0x8, x, y, 0x0),
+            Instruction::Ins8XY1(x, y) => xy(0x8, x, y, 0x1),
+            Instruction::Ins8XY2(x, y) => xy(0x8, x, y, 0x2),
+            Instruction::Ins8XY3(x, y) => xy(0x8, x, y, 0x3),
+            Instruction::Ins8XY4(x, y) => xy(0x8, x, y, 0x4),
+            Instruction::Ins8XY5(x, y) => xy(0x8, x, y, 0x5),
+            Instruction::Ins8XY6(x, y) => xy(0x8, x, y, 0x6),
+            Instruction::Ins8XY7(x, y) => xy(0x8, x, y, 0x7),
+            Instruction::Ins8XYE(x, y) => xy(0x8, x, y, 0xE),
+            Instruction::Ins9XY0(x, y) => xy(0x9, x, y, 0x0),
+            Instruction::InsANNN(nnn) => 0xA000 | nnn & 0x0FFF,
+            Instruction::InsBNNN(nnn) => 0xB000 | nnn & 0x0FFF,
+            Instruction::InsCXNN(x, nn) => xnn(0xC, x, nn),
+            Instruction::InsDXYN(x, y, n) => xy(0xD, x, y, n as u16),
+            Instruction::InsEX9E(x) => xnn(0xE, x, 0x9E),
+            Instruction::InsEXA1(x) => xnn(0xE, x, 0xA1),
+            Instruction::InsFX07(x) => xnn(0xF, x, 0x07),
+            Instruction::InsFX0A(x) => xnn(0xF, x, 0x0A),
+            Instruction::InsFX15(x) => xnn(0xF, x, 0x15),
+            Instruction::InsFX18(x) => xnn(0xF, x, 0x18),
+            Instruction::InsFX1E(x) => xnn(0xF, x, 0x1E),
+            Instruction::InsFX29(x) => xnn(0xF, x, 0x29),
+            Instruction::InsFX33(x) => xnn(0xF, x, 0x33),
+            Instruction::InsFX55(x) => xnn(0xF, x, 0x55),
+            Instruction::InsFX65(x) => xnn(0xF, x, 0x65),
+            Instruction::InsF002 => 0xF002,
+            Instruction::InsFX3A(x) => xnn(0xF, x, 0x3A),
+            Instruction::InsFN01(n) => xnn(0xF, n, 0x01),
+            Instruction::InsUnknown(opcode) => opcode,
+        }
+    }
+
+    // 宽松解码: 5XYN/9XYN 的未知尾数按 5XY0/9XY0 处理
+    // 其余无法识别的操作码解码为 InsUnknown 空操作, 因此总能成功
+    pub fn decode_lenient(opcode: u16) -> Self {
+        Instruction::try_from(opcode).unwrap_or_else(|_| Instruction::lenient_fallback(opcode))
+    }
+
+    // 无法解码的操作码在宽松模式下对应的指令
+    pub fn lenient_fallback(opcode: u16) -> Self {
+        let (n1, n2, n3, _) = Instruction::nibbles(opcode);
+        match n1 {
+            0x5 => Instruction::Ins5XY0(n2, n3),
+            0x9 => Instruction::Ins9XY0(n2, n3),
+            _ => Instruction::InsUnknown(opcode),
+        }
+    }
+
+    // 仅在 XO-CHIP 模式下有效的扩展指令
+    pub fn is_xo_chip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::InsF002 | Instruction::InsFX3A(_) | Instruction::InsFN01(_)
+        )
+    }
+
+    // 各操作数的访问器, 指令不含该操作数时返回 None
+    pub fn x(&self) -> Option<u8> {
+        match *self {
+            Instruction::Ins3XNN(x, _)
+            | Instruction::Ins4XNN(x, _)
+            | Instruction::Ins5XY0(x, _)
+            | Instruction::Ins6XNN(x, _)
+            | Instruction::Ins7XNN(x, _)
+            | Instruction::Ins8XY0(x, _)
+            | Instruction::Ins8XY1(x, _)
+            | Instruction::Ins8XY2(x, _)
+            | Instruction::Ins8XY3(x, _)
+            | Instruction::Ins8XY4(x, _)
+            | Instruction::Ins8XY5(x, _)
+            | Instruction::Ins8XY6(x, _)
+            | Instruction::Ins8XY7(x, _)
+            | Instruction::Ins8XYE(x, _)
+            | Instruction::Ins9XY0(x, _)
+            | Instruction::InsCXNN(x, _)
+            | Instruction::InsDXYN(x, _, _)
+            | Instruction::InsEX9E(x)
+            | Instruction::InsEXA1(x)
+            | Instruction::InsFX07(x)
+            | Instruction::InsFX0A(x)
+            | Instruction::InsFX15(x)
+            | Instruction::InsFX18(x)
+            | Instruction::InsFX1E(x)
+            | Instruction::InsFX29(x)
+            | Instruction::InsFX33(x)
+            | Instruction::InsFX55(x)
+            | Instruction::InsFX65(x)
+            | Instruction::InsFX3A(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn y(&self) -> Option<u8> {
+        match *self {
+            Instruction::Ins5XY0(_, y)
+            | Instruction::Ins8XY0(_, y)
+            | Instruction::Ins8XY1(_, y)
+            | Instruction::Ins8XY2(_, y)
+            | Instruction::Ins8XY3(_, y)
+            | Instruction::Ins8XY4(_, y)
+            | Instruction::Ins8XY5(_, y)
+            | Instruction::Ins8XY6(_, y)
+            | Instruction::Ins8XY7(_, y)
+            | Instruction::Ins8XYE(_, y)
+            | Instruction::Ins9XY0(_, y)
+            | Instruction::InsDXYN(_, y, _) => Some(y),
+            _ => None,
+        }
+    }
+
+    pub fn nnn(&self) -> Option<u16> {
+        match *self {
+            Instruction::Ins0NNN(nnn)
+            | Instruction::Ins1NNN(nnn)
+            | Instruction::Ins2NNN(nnn)
+            | Instruction::InsANNN(nnn)
+            | Instruction::InsBNNN(nnn) => Some(nnn),
+            _ => None,
+        }
+    }
+
+    pub fn nn(&self) -> Option<u8> {
+        match *self {
+            Instruction::Ins3XNN(_, nn)
+            | Instruction::Ins4XNN(_, nn)
+            | Instruction::Ins6XNN(_, nn)
+            | Instruction::Ins7XNN(_, nn)
+            | Instruction::InsCXNN(_, nn) => Some(nn),
+            _ => None,
+        }
+    }
+
+    pub fn n(&self) -> Option<u8> {
+        match *self {
+            // FN01 的操作数是平面掩码, 不是寄存器
+            Instruction::InsDXYN(_, _, n) | Instruction::InsFN01(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+// COSMAC VIP 上的近似机器周期数, 用于按周期控制帧率
+// DXYN 与行数相关, FX55/FX65 与寄存器数量相关
+pub fn cost(instruction: &Instruction) -> u32 {
+    match *instruction {
+        Instruction::Ins00E0 => 24,
+        Instruction::Ins00EE => 10,
+        Instruction::Ins0NNN(_) => 12,
+        Instruction::Ins00FD => 10,
+        Instruction::Ins1NNN(_) => 12,
+        Instruction::Ins2NNN(_) => 26,
+        Instruction::Ins3XNN(..) | Instruction::Ins4XNN(..) => 10,
+        Instruction::Ins5XY0(..) | Instruction::Ins9XY0(..) => 14,
+        Instruction::Ins6XNN(..) => 6,
+        Instruction::Ins7XNN(..) => 10,
+        Instruction::Ins8XY0(..)
+        | Instruction::Ins8XY1(..)
+        | Instruction::Ins8XY2(..)
+        | Instruction::Ins8XY3(..)
+        | Instruction::Ins8XY4(..)
+        | Instruction::Ins8XY5(..)
+        | Instruction::Ins8XY6(..)
+        | Instruction::Ins8XY7(..)
+        | Instruction::Ins8XYE(..) => 44,
+        Instruction::InsANNN(_) => 12,
+        Instruction::InsBNNN(_) => 22,
+        Instruction::InsCXNN(..) => 36,
+        Instruction::InsDXYN(_, _, n) => 22 + 46 * n as u32,
+        Instruction::InsEX9E(_) | Instruction::InsEXA1(_) => 14,
+        Instruction::InsFX07(_) | Instruction::InsFX15(_) | Instruction::InsFX18(_) => 10,
+        Instruction::InsFX0A(_) => 18,
+        Instruction::InsFX1E(_) => 16,
+        Instruction::InsFX29(_) => 20,
+        Instruction::InsFX33(_) => 80,
+        Instruction::InsFX55(x) | Instruction::InsFX65(x) => 14 + 14 * (x as u32 + 1),
+        // COSMAC VIP 上没有这两条指令, 按同类指令估计
+        Instruction::InsF002 => 14 + 14 * 16,
+        Instruction::InsFX3A(_) => 10,
+        Instruction::InsFN01(_) => 10,
+        Instruction::InsUnknown(_) => 10,
+    }
+}
+
+impl From<&Instruction> for u16 {
+    fn from(instruction: &Instruction) -> Self {
+        instruction.to_opcode()
+    }
 }
 
 impl TryFrom<u16> for Instruction {
@@ -69,20 +399,24 @@ impl TryFrom<u16> for Instruction {
             (0x0, 0x0, 0xE, 0x0) => Ok(Instruction::Ins00E0),
             // ret
             (0x0, 0x0, 0xE, 0xE) => Ok(Instruction::Ins00EE),
+            // exit
+            (0x0, 0x0, 0xF, 0xD) => Ok(Instruction::Ins00FD),
+            // SYS NNN
+            (0x0, _, _, _) => Ok(Instruction::Ins0NNN(Instruction::nnn_of(opcode))),
             // jmp NNN
-            (0x1, _, _, _) => Ok(Instruction::Ins1NNN(Instruction::nnn(opcode))),
+            (0x1, _, _, _) => Ok(Instruction::Ins1NNN(Instruction::nnn_of(opcode))),
             // CALL NNN
-            (0x2, _, _, _) => Ok(Instruction::Ins2NNN(Instruction::nnn(opcode))),
+            (0x2, _, _, _) => Ok(Instruction::Ins2NNN(Instruction::nnn_of(opcode))),
             // SKIP VX == NN
-            (0x3, _, _, _) => Ok(Instruction::Ins3XNN(n2, Instruction::nn(opcode))),
+            (0x3, _, _, _) => Ok(Instruction::Ins3XNN(n2, Instruction::nn_of(opcode))),
             // SKIP VX != NN
-            (0x4, _, _, _) => Ok(Instruction::Ins4XNN(n2, Instruction::nn(opcode))),
+            (0x4, _, _, _) => Ok(Instruction::Ins4XNN(n2, Instruction::nn_of(opcode))),
             // SKIP VX == VY
             (0x5, _, _, 0x0) => Ok(Instruction::Ins5XY0(n2, n3)),
             // VX = NN
-            (0x6, _, _, _) => Ok(Instruction::Ins6XNN(n2, Instruction::nn(opcode))),
+            (0x6, _, _, _) => Ok(Instruction::Ins6XNN(n2, Instruction::nn_of(opcode))),
             // VX += NN
-            (0x7, _, _, _) => Ok(Instruction::Ins7XNN(n2, Instruction::nn(opcode))),
+            (0x7, _, _, _) => Ok(Instruction::Ins7XNN(n2, Instruction::nn_of(opcode))),
             // VX = VY
             (0x8, _, _, 0x0) => Ok(Instruction::Ins8XY0(n2, n3)),
             // VX |= VY
@@ -104,11 +438,11 @@ impl TryFrom<u16> for Instruction {
             // SKIP VX != VY
             (0x9, _, _, 0x0) => Ok(Instruction::Ins9XY0(n2, n3)),
             // I = NNN
-            (0xA, _, _, _) => Ok(Instruction::InsANNN(Instruction::nnn(opcode))),
+            (0xA, _, _, _) => Ok(Instruction::InsANNN(Instruction::nnn_of(opcode))),
             // JMP V0 + NNN
-            (0xB, _, _, _) => Ok(Instruction::InsBNNN(Instruction::nnn(opcode))),
+            (0xB, _, _, _) => Ok(Instruction::InsBNNN(Instruction::nnn_of(opcode))),
             // VX = rand() & NN
-            (0xC, _, _, _) => Ok(Instruction::InsCXNN(n2, Instruction::nn(opcode))),
+            (0xC, _, _, _) => Ok(Instruction::InsCXNN(n2, Instruction::nn_of(opcode))),
             // DRAW
             (0xD, _, _, _) => Ok(Instruction::InsDXYN(n2, n3, n4)),
             // SKIP KEY PRESS
@@ -133,6 +467,12 @@ impl TryFrom<u16> for Instruction {
             (0xF, _, 0x5, 0x5) => Ok(Instruction::InsFX55(n2)),
             // LOAD V0 - VX
             (0xF, _, 0x6, 0x5) => Ok(Instruction::InsFX65(n2)),
+            // AUDIO PATTERN
+            (0xF, 0x0, 0x0, 0x2) => Ok(Instruction::InsF002),
+            // PITCH = VX
+            (0xF, _, 0x3, 0xA) => Ok(Instruction::InsFX3A(n2)),
+            // PLANE N
+            (0xF, _, 0x0, 0x1) => Ok(Instruction::InsFN01(n2)),
             // err
             _ => Err(InstructionError::UnknownOpcode(opcode)),
         }
@@ -156,6 +496,24 @@ mod tests {
             Instruction::try_from(0x00EE),
             Ok(Instruction::Ins00EE)
         ));
+
+        // 00FD - EXIT
+        assert!(matches!(
+            Instruction::try_from(0x00FD),
+            Ok(Instruction::Ins00FD)
+        ));
+    }
+
+    #[test]
+    fn test_sys_instruction() {
+        assert!(matches!(
+            Instruction::try_from(0x0123),
+            Ok(Instruction::Ins0NNN(0x123))
+        ));
+        assert!(matches!(
+            Instruction::try_from(0x00F1),
+            Ok(Instruction::Ins0NNN(0x0F1))
+        ));
     }
 
     #[test]
@@ -233,6 +591,21 @@ mod tests {
             Instruction::try_from(opcode),
             Ok(Instruction::InsFX55(0xA))
         ));
+
+        // XO-CHIP 音频
+        assert!(matches!(
+            Instruction::try_from(0xF002),
+            Ok(Instruction::InsF002)
+        ));
+        assert!(matches!(
+            Instruction::try_from(0xF53A),
+            Ok(Instruction::InsFX3A(0x5))
+        ));
+        assert!(Instruction::try_from(0xF102).is_err());
+        assert!(matches!(
+            Instruction::try_from(0xF301),
+            Ok(Instruction::InsFN01(0x3))
+        ));
     }
 
     #[test]
@@ -257,14 +630,14 @@ mod tests {
     fn test_invalid_opcodes() {
         // 未知指令
         assert!(matches!(
-            Instruction::try_from(0x0001),
-            Err(InstructionError::UnknownOpcode(0x0001))
+            Instruction::try_from(0xE09F),
+            Err(InstructionError::UnknownOpcode(0xE09F))
         ));
 
-        // 非法 0x0 前缀指令
+        // 非法 0xF 前缀指令
         assert!(matches!(
-            Instruction::try_from(0x00F1),
-            Err(InstructionError::UnknownOpcode(0x00F1))
+            Instruction::try_from(0xF0FF),
+            Err(InstructionError::UnknownOpcode(0xF0FF))
         ));
 
         // 非法 8XY 格式
@@ -274,6 +647,108 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_opcode_pattern() {
+        assert_eq!(Instruction::Ins8XY4(1, 2).opcode_pattern(), "8XY4");
+        assert_eq!(Instruction::InsANNN(0x123).opcode_pattern(), "ANNN");
+        assert_eq!(Instruction::InsFX33(0).opcode_pattern(), "FX33");
+        assert_eq!(Instruction::Ins0NNN(0).opcode_pattern(), "0NNN");
+    }
+
+    #[test]
+    fn test_instruction_kind() {
+        assert_eq!(
+            Instruction::Ins1NNN(0x200).kind(),
+            InstructionKind::FlowControl
+        );
+        assert_eq!(
+            Instruction::Ins8XY4(0, 1).kind(),
+            InstructionKind::Arithmetic
+        );
+        assert_eq!(
+            Instruction::InsDXYN(0, 1, 5).kind(),
+            InstructionKind::Display
+        );
+        assert_eq!(Instruction::InsFX0A(0).kind(), InstructionKind::Input);
+        assert_eq!(Instruction::InsFX15(0).kind(), InstructionKind::Timer);
+        assert_eq!(Instruction::InsF002.kind(), InstructionKind::Sound);
+        assert_eq!(Instruction::InsFX3A(0).kind(), InstructionKind::Sound);
+    }
+
+    #[test]
+    fn test_decode_lenient() {
+        assert!(Instruction::try_from(0x5AB1).is_err());
+        assert!(matches!(
+            Instruction::decode_lenient(0x5AB1),
+            Instruction::Ins5XY0(0xA, 0xB)
+        ));
+        assert!(matches!(
+            Instruction::decode_lenient(0x9125),
+            Instruction::Ins9XY0(1, 2)
+        ));
+        for opcode in [0x812F, 0xE3FF, 0xF4FF] {
+            let ins = Instruction::decode_lenient(opcode);
+            assert!(matches!(ins, Instruction::InsUnknown(raw) if raw == opcode));
+            assert_eq!(ins.to_opcode(), opcode);
+            assert_eq!(ins.opcode_pattern(), "????");
+            assert_eq!(ins.kind(), InstructionKind::Misc);
+        }
+        // 合法操作码不受影响
+        assert!(matches!(
+            Instruction::decode_lenient(0x8124),
+            Instruction::Ins8XY4(1, 2)
+        ));
+    }
+
+    #[test]
+    fn test_operands() {
+        let ins = Instruction::Ins3XNN(0x4, 0x2A);
+        assert_eq!(ins.x(), Some(0x4));
+        assert_eq!(ins.nn(), Some(0x2A));
+        assert_eq!(ins.nnn(), None);
+        assert_eq!(ins.y(), None);
+
+        let ins = Instruction::InsANNN(0x123);
+        assert_eq!(ins.nnn(), Some(0x123));
+        assert_eq!(
+            (ins.x(), ins.y(), ins.nn(), ins.n()),
+            (None, None, None, None)
+        );
+
+        let ins = Instruction::InsDXYN(1, 2, 3);
+        assert_eq!((ins.x(), ins.y(), ins.n()), (Some(1), Some(2), Some(3)));
+
+        let ins = Instruction::InsFN01(0b11);
+        assert_eq!((ins.x(), ins.n()), (None, Some(0b11)));
+    }
+
+    #[test]
+    fn test_to_opcode_round_trip() {
+        for opcode in [0x6A2A, 0xD123, 0xA2F0, 0x8AB5] {
+            let ins = Instruction::try_from(opcode).unwrap();
+            assert_eq!(ins.to_opcode(), opcode);
+            assert_eq!(u16::from(&ins), opcode);
+        }
+        // 所有合法操作码都能原样还原
+        for opcode in 0..=u16::MAX {
+            if let Ok(ins) = Instruction::try_from(opcode) {
+                assert_eq!(ins.to_opcode(), opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_instruction_cost_ordering() {
+        let ld = cost(&Instruction::Ins6XNN(0, 0));
+        let add = cost(&Instruction::Ins8XY4(0, 1));
+        let bcd = cost(&Instruction::InsFX33(0));
+        assert!(ld < add);
+        assert!(add < bcd);
+        // 行数越多越慢
+        assert!(cost(&Instruction::InsDXYN(0, 0, 1)) < cost(&Instruction::InsDXYN(0, 0, 15)));
+        assert!(bcd < cost(&Instruction::InsDXYN(0, 0, 15)));
+    }
+
     #[test]
     fn test_edge_cases() {
         // 最大地址测试
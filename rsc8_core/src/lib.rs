@@ -1,5 +1,18 @@
 #![no_std]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod checksum;
 pub mod chip8;
 pub mod error;
+pub mod ffi;
+pub mod input;
 pub mod instruction;
+pub mod quirks;
 pub mod rng;
+pub mod screen;
+pub mod session;
+pub mod time;
+
+pub use chip8::{SCREEN_HEIGHT, SCREEN_WIDTH};
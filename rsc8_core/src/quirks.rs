@@ -0,0 +1,234 @@
+use core::str::FromStr;
+
+use crate::error::ParseQuirkError;
+
+// 不同解释器之间的行为差异, 默认值遵循 COSMAC VIP
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    // DXYN 开始时先将 VF 清零; 关闭时只有实际绘制了像素才写 VF
+    pub vf_reset_on_draw: bool,
+    // 8XY1/8XY2/8XY3 执行后将 VF 清零, CHIP-48/SCHIP 不清零
+    pub logic_clears_vf: bool,
+    // DXYN 等待垂直同步, 每帧(两次计时器更新之间)最多绘制一次
+    pub display_wait: bool,
+    // FX1E 使 I 超过 0xFFF 时置 VF=1 (Amiga 解释器行为)
+    pub i_overflow_sets_vf: bool,
+    // 8XY6/8XYE 先将 VY 复制到 VX 再移位, CHIP-48/SCHIP 直接移位 VX
+    pub shift_uses_vy: bool,
+    // FX55/FX65 执行后 I 增加 X+1, SCHIP 保持 I 不变
+    pub load_store_increments_i: bool,
+    // DXYN 越过右/下边缘的像素回绕到另一侧, 关闭时裁剪 (起始坐标总是取模)
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    // SCHIP 高分辨率下 DXYN 将 VF 设为发生碰撞的行数加上被底边裁掉的行数
+    pub schip_collision_rows: bool,
+    // 8XY5/8XY7 发生借位时 VF=1, 默认相反 (VF=1 表示没有借位)
+    pub subtract_borrow_inverted: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quirks {
+    pub const fn new() -> Self {
+        Self {
+            vf_reset_on_draw: true,
+            logic_clears_vf: true,
+            display_wait: false,
+            i_overflow_sets_vf: false,
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            wrap_x: false,
+            wrap_y: false,
+            schip_collision_rows: false,
+            subtract_borrow_inverted: false,
+        }
+    }
+
+    // 原版 COSMAC VIP 解释器
+    pub const fn cosmac_vip() -> Self {
+        Self {
+            display_wait: true,
+            ..Self::new()
+        }
+    }
+
+    // HP48 上的 CHIP-48; 其 FX55/FX65 实际使 I 增加 X, 这里按增加 X+1 近似
+    pub const fn chip48() -> Self {
+        Self {
+            logic_clears_vf: false,
+            shift_uses_vy: false,
+            ..Self::new()
+        }
+    }
+
+    // SCHIP 1.1
+    pub const fn superchip() -> Self {
+        Self {
+            logic_clears_vf: false,
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            schip_collision_rows: true,
+            ..Self::new()
+        }
+    }
+
+    // XO-CHIP 的精灵在边缘回绕而不是裁剪
+    pub const fn xochip() -> Self {
+        Self {
+            logic_clears_vf: false,
+            wrap_x: true,
+            wrap_y: true,
+            ..Self::new()
+        }
+    }
+
+    // 按 ROM 的 CRC32 在内置表中查找推荐配置
+    pub fn lookup(checksum: u32) -> Option<Quirks> {
+        Self::lookup_in(KNOWN_ROMS, checksum)
+    }
+
+    // 在前端自带的 ROM 表中查找
+    pub fn lookup_in(table: &[KnownRom], checksum: u32) -> Option<Quirks> {
+        table
+            .iter()
+            .find(|rom| rom.checksum == checksum)
+            .map(|rom| rom.quirks)
+    }
+}
+
+// 每行一个 key=value, 未列出的项保持默认值, 空行和 # 开头的行被忽略
+impl FromStr for Quirks {
+    type Err = ParseQuirkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quirks = Quirks::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ParseQuirkError::MissingValue(line_no))?;
+            let value = match value.trim() {
+                "true" => true,
+                "false" => false,
+                _ => return Err(ParseQuirkError::InvalidValue(line_no)),
+            };
+            let field = match key.trim() {
+                "vf_reset_on_draw" => &mut quirks.vf_reset_on_draw,
+                "logic_clears_vf" => &mut quirks.logic_clears_vf,
+                "display_wait" => &mut quirks.display_wait,
+                "i_overflow_sets_vf" => &mut quirks.i_overflow_sets_vf,
+                "shift_uses_vy" => &mut quirks.shift_uses_vy,
+                "load_store_increments_i" => &mut quirks.load_store_increments_i,
+                "wrap_x" => &mut quirks.wrap_x,
+                "wrap_y" => &mut quirks.wrap_y,
+                "schip_collision_rows" => &mut quirks.schip_collision_rows,
+                "subtract_borrow_inverted" => &mut quirks.subtract_borrow_inverted,
+                _ => return Err(ParseQuirkError::UnknownKey(line_no)),
+            };
+            *field = value;
+        }
+        Ok(quirks)
+    }
+}
+
+pub struct KnownRom {
+    pub checksum: u32,
+    pub title: &'static str,
+    pub quirks: Quirks,
+}
+
+// 已知 ROM 的 CRC32 与推荐配置
+pub const KNOWN_ROMS: &[KnownRom] = &[
+    KnownRom {
+        checksum: 0x044B_E964,
+        title: "clzz logo",
+        quirks: Quirks::new(),
+    },
+    KnownRom {
+        checksum: 0xC46C_A868,
+        title: "IBM Logo",
+        quirks: Quirks::new(),
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(Quirks::lookup(0x044B_E964), Some(Quirks::new()));
+        assert_eq!(Quirks::lookup(0xC46C_A868), Some(Quirks::new()));
+        assert_eq!(Quirks::lookup(0), None);
+
+        let table = [KnownRom {
+            checksum: 0x1234_5678,
+            title: "test",
+            quirks: Quirks::superchip(),
+        }];
+        assert_eq!(
+            Quirks::lookup_in(&table, 0x1234_5678),
+            Some(Quirks::superchip())
+        );
+        assert_eq!(Quirks::lookup_in(&table, 0x044B_E964), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let profile = "
+            # CHIP-48
+            shift_uses_vy=false
+            logic_clears_vf = false
+            load_store_increments_i=false
+            display_wait=true
+            wrap_x=true
+        ";
+        let quirks = Quirks::from_str(profile).unwrap();
+        assert!(!quirks.shift_uses_vy);
+        assert!(!quirks.logic_clears_vf);
+        assert!(!quirks.load_store_increments_i);
+        assert!(quirks.display_wait);
+        assert!(quirks.wrap_x && !quirks.wrap_y);
+        // 未出现的项保持默认
+        assert!(quirks.vf_reset_on_draw);
+        assert!(!quirks.i_overflow_sets_vf);
+
+        assert!(matches!(
+            Quirks::from_str("display_wait=true\nclip=false"),
+            Err(ParseQuirkError::UnknownKey(2))
+        ));
+        assert!(matches!(
+            "shift_uses_vy=yes".parse::<Quirks>(),
+            Err(ParseQuirkError::InvalidValue(1))
+        ));
+        assert!(matches!(
+            "shift_uses_vy".parse::<Quirks>(),
+            Err(ParseQuirkError::MissingValue(1))
+        ));
+    }
+
+    #[test]
+    fn test_presets() {
+        let vip = Quirks::cosmac_vip();
+        let schip = Quirks::superchip();
+        assert!(vip.shift_uses_vy && !schip.shift_uses_vy);
+        assert!(vip.load_store_increments_i && !schip.load_store_increments_i);
+        assert!(vip.logic_clears_vf && !schip.logic_clears_vf);
+        assert!(vip.display_wait && !schip.display_wait);
+        assert!(!Quirks::chip48().shift_uses_vy);
+        assert!(!vip.schip_collision_rows && schip.schip_collision_rows);
+        assert!(!vip.wrap_x && !schip.wrap_x);
+        let xochip = Quirks::xochip();
+        assert!(xochip.shift_uses_vy);
+        assert!(xochip.wrap_x && xochip.wrap_y);
+        assert!(!xochip.schip_collision_rows);
+    }
+}
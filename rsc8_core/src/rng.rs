@@ -22,3 +22,16 @@ impl Iterator for LinearCongruentialGenerator {
         Some(self.seed)
     }
 }
+
+// 始终返回同一个值, 便于测试 CXNN
+pub struct MockRng {
+    pub value: u16,
+}
+
+impl Iterator for MockRng {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.value)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::chip8::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// 精灵绘制方式, Xor 为标准行为, Overwrite 直接覆盖且不做碰撞检测
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawMode {
+    #[default]
+    Xor,
+    Overwrite,
+}
+
+// 屏幕缓冲区, 每行打包为一个 u128, 第 x 列对应第 127 - x 位(最高位为最左侧像素)
+// 越界访问一律忽略, 宽度最多 128
+#[derive(Clone)]
+pub struct Screen<const W: usize = SCREEN_WIDTH, const H: usize = SCREEN_HEIGHT> {
+    rows: [u128; H],
+    dirty: Option<(usize, usize, usize, usize)>, // 自上次 take_dirty_rect 以来改变的区域(闭区间)
+}
+
+impl<const W: usize, const H: usize> Default for Screen<W, H> {
+    fn default() -> Self {
+        // 在编译期拒绝超过 128 列的屏幕
+        let _ = Self::ROW_MASK;
+        Self {
+            rows: [0; H],
+            dirty: None,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Screen<W, H> {
+    // 每行中属于屏幕的位
+    pub const ROW_MASK: u128 = {
+        assert!(W > 0 && W <= 128, "屏幕宽度必须在 1..=128 之间");
+        !0 << (128 - W)
+    };
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < W && y < H && self.rows[y] & Self::bit(x) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if x < W && y < H && self.get(x, y) != on {
+            self.rows[y] ^= Self::bit(x);
+            self.mark_dirty(x, y, x, y);
+        }
+    }
+
+    // 异或绘制, 返回是否发生碰撞(原本点亮的像素被擦除)
+    pub fn xor(&mut self, x: usize, y: usize, on: bool) -> bool {
+        if x >= W || y >= H || !on {
+            return false;
+        }
+        self.xor_row(y, Self::bit(x)) != 0
+    }
+
+    // 整行异或, bits 的布局与 row 相同, 返回发生碰撞的位
+    pub fn xor_row(&mut self, y: usize, bits: u128) -> u128 {
+        let bits = bits & Self::ROW_MASK;
+        if y >= H || bits == 0 {
+            return 0;
+        }
+        let collided = self.rows[y] & bits;
+        self.rows[y] ^= bits;
+        self.mark_row_dirty(y, bits);
+        collided
+    }
+
+    // 将 mask 覆盖的位替换为 bits, 返回修改前的整行
+    pub fn write_row(&mut self, y: usize, mask: u128, bits: u128) -> u128 {
+        if y >= H {
+            return 0;
+        }
+        let old = self.rows[y];
+        self.rows[y] = (old & !mask) | (bits & mask & Self::ROW_MASK);
+        self.mark_row_dirty(y, old ^ self.rows[y]);
+        old
+    }
+
+    // 打包后的一行, 越界时返回 0
+    pub fn row(&self, y: usize) -> u128 {
+        self.rows.get(y).copied().unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.rows = [0; H];
+        self.mark_dirty(0, 0, W - 1, H - 1);
+    }
+
+    // 返回并重置脏矩形 (x0, y0, x1, y1), 坐标均包含在内
+    pub fn take_dirty_rect(&mut self) -> Option<(usize, usize, usize, usize)> {
+        self.dirty.take()
+    }
+
+    const fn bit(x: usize) -> u128 {
+        1 << (127 - x)
+    }
+
+    fn mark_row_dirty(&mut self, y: usize, changed: u128) {
+        if changed != 0 {
+            let x0 = changed.leading_zeros() as usize;
+            let x1 = 127 - changed.trailing_zeros() as usize;
+            self.mark_dirty(x0, y, x1, y);
+        }
+    }
+
+    fn mark_dirty(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        self.dirty = Some(match self.dirty {
+            Some((dx0, dy0, dx1, dy1)) => (dx0.min(x0), dy0.min(y0), dx1.max(x1), dy1.max(y1)),
+            None => (x0, y0, x1, y1),
+        });
+    }
+
+    // 逐行展开为 bool
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool>> + '_ {
+        self.rows
+            .iter()
+            .map(|&row| (0..W).map(move |x| row & Self::bit(x) != 0))
+    }
+
+    // 一维视图, 顺序为 x + y * W
+    pub fn pixels(&self) -> impl Iterator<Item = bool> + '_ {
+        self.rows().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xor_collision() {
+        let mut screen: Screen = Screen::default();
+        assert!(!screen.xor(3, 4, true));
+        assert!(screen.get(3, 4));
+        // 再次绘制同一位置, 像素被擦除并报告碰撞
+        assert!(screen.xor(3, 4, true));
+        assert!(!screen.get(3, 4));
+        // 未点亮的精灵像素不会引起碰撞
+        screen.set(5, 5, true);
+        assert!(!screen.xor(5, 5, false));
+        assert!(screen.get(5, 5));
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut screen: Screen = Screen::default();
+        screen.set(SCREEN_WIDTH - 1, 1, true);
+        assert_eq!(screen.rows().count(), SCREEN_HEIGHT);
+        assert!(screen.rows().all(|row| row.count() == SCREEN_WIDTH));
+        assert!(screen.rows().nth(1).unwrap().nth(SCREEN_WIDTH - 1).unwrap());
+        assert!(screen.pixels().nth(SCREEN_WIDTH * 2 - 1).unwrap());
+        assert_eq!(screen.pixels().filter(|&p| p).count(), 1);
+    }
+
+    #[test]
+    fn test_dirty_rect() {
+        let mut screen: Screen = Screen::default();
+        assert_eq!(screen.take_dirty_rect(), None);
+        screen.xor(4, 2, true);
+        screen.xor(9, 1, false); // 未改变像素
+        screen.set(6, 7, true);
+        assert_eq!(screen.take_dirty_rect(), Some((4, 2, 6, 7)));
+        assert_eq!(screen.take_dirty_rect(), None);
+        screen.clear();
+        assert_eq!(
+            screen.take_dirty_rect(),
+            Some((0, 0, SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1))
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut screen: Screen = Screen::default();
+        screen.set(SCREEN_WIDTH, 0, true);
+        assert!(!screen.xor(0, SCREEN_HEIGHT, true));
+        assert!(!screen.get(SCREEN_WIDTH, 0));
+        screen.clear();
+        assert!(screen.pixels().all(|p| !p));
+    }
+
+    #[test]
+    fn test_row_ops() {
+        let mut screen: Screen = Screen::default();
+        // 0xF0 画在第 60 列, 超出屏幕的 4 位被丢弃
+        let bits = 0xF0u128 << 120 >> 60;
+        assert_eq!(screen.xor_row(0, bits), 0);
+        assert_eq!(screen.take_dirty_rect(), Some((60, 0, 63, 0)));
+        assert!((60..64).all(|x| screen.get(x, 0)));
+        assert_eq!(screen.row(0).count_ones(), 4);
+        assert_eq!(screen.xor_row(0, bits), bits & Screen::<64, 32>::ROW_MASK);
+        assert_eq!(screen.row(0), 0);
+
+        screen.set(1, 2, true);
+        let old = screen.write_row(2, 0xFF << 120, 0x81 << 120);
+        assert_eq!(old, 1 << 126);
+        assert!(screen.get(0, 2) && !screen.get(1, 2) && screen.get(7, 2));
+        assert_eq!(screen.row(SCREEN_HEIGHT), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+
+use crate::{
+    chip8::Chip8, error::SessionError, input::KeyScript, quirks::Quirks,
+    rng::LinearCongruentialGenerator,
+};
+
+// 复现一次运行所需的全部输入, 附在 bug 报告中
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub rom: Vec<u8>,
+    pub seed: u16, // LinearCongruentialGenerator 的初始种子
+    pub quirks: Quirks,
+    pub xo_chip: bool,
+    pub strict_decode: bool,
+    pub cycles_per_frame: u32, // 每执行多少次 tick 更新一次 60Hz 计时器, 0 表示不更新
+    pub key_script: KeyScript, // 按指令周期记录的按键事件
+}
+
+impl<const W: usize, const H: usize> Chip8<LinearCongruentialGenerator, W, H> {
+    // 重置后按 session 载入 ROM, 种子和模式设置, 执行 ticks 次 tick,
+    // 在对应周期应用按键并按 cycles_per_frame 更新计时器
+    // 暂停, 锁定的寄存器, turbo 等运行时状态会被清除, draw_mode, runaway_threshold, 调用栈深度等
+    // 设置恢复为默认值, 当前的 key_script 会被替换; 回放中途暂停时返回 SessionError::Paused
+    pub fn play_session(&mut self, session: &Session, ticks: usize) -> Result<(), SessionError> {
+        self.reset_for_replay();
+        self.quirks = session.quirks;
+        self.xo_chip = session.xo_chip;
+        self.strict_decode = session.strict_decode;
+        self.rng.seed = session.seed;
+        self.load_rom(&session.rom)?;
+        self.key_script = session.key_script.clone();
+        self.key_script.rewind();
+        let frame = session.cycles_per_frame as usize;
+        for n in 1..=ticks {
+            self.apply_key_events_at(self.cycle_count);
+            self.tick()?;
+            if self.paused {
+                return Err(SessionError::Paused(self.cycle_count));
+            }
+            if frame > 0 && n % frame == 0 {
+                self.tick_timer();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checksum::crc32, chip8::STACK_SIZE, screen::DrawMode};
+    use alloc::vec;
+
+    fn screen_hash(c8: &Chip8<LinearCongruentialGenerator>) -> u32 {
+        let pixels: Vec<u8> = c8.get_display().iter().map(|&on| on as u8).collect();
+        crc32(&pixels)
+    }
+
+    fn session(rom: Vec<u8>, key_script: KeyScript) -> Session {
+        Session {
+            rom,
+            seed: 0x1234,
+            quirks: Quirks::default(),
+            xo_chip: false,
+            strict_decode: true,
+            cycles_per_frame: 10,
+            key_script,
+        }
+    }
+
+    #[test]
+    fn test_play_session() {
+        let mut key_script = KeyScript::new();
+        key_script.push(20, 0x0, true);
+        key_script.push(40, 0x0, false);
+        let session = session(
+            vec![
+                0xC0, 0x3F, // V0 = rand & 0x3F
+                0xC1, 0x1F, // V1 = rand & 0x1F
+                0xA0, 0x00, // I = 字符 0
+                0xE2, 0xA1, // 按键 V2 没有按下时跳过
+                0xA0, 0x05, // I = 字符 1
+                0xD0, 0x15, // 绘制
+                0x12, 0x00, // 循环
+            ],
+            key_script,
+        );
+
+        let mut a = Chip8::new(LinearCongruentialGenerator::default());
+        let mut b = Chip8::with_stack_depth(LinearCongruentialGenerator { seed: 99 }, 1);
+        // 之前的运行时状态和设置不影响回放
+        b.pause();
+        b.turbo = true;
+        b.freeze_register(0, 0);
+        b.set_memory_fill(0xFF);
+        b.runaway_threshold = Some(5);
+        b.draw_mode = DrawMode::Overwrite;
+        b.halt_on_unknown_opcode = true;
+        a.play_session(&session, 70).unwrap();
+        b.play_session(&session, 70).unwrap();
+        assert_eq!(a.cycle_count, 70);
+        assert_eq!(b.cycle_count, 70);
+        assert!(a.get_display().iter().any(|&p| p));
+        assert_eq!(screen_hash(&a), screen_hash(&b));
+        assert!(!b.is_paused());
+        assert_eq!(b.draw_mode, DrawMode::Xor);
+        assert_eq!(b.runaway_threshold, None);
+        assert_eq!(b.stack.len(), STACK_SIZE);
+
+        // 重复播放结果相同
+        let hash = screen_hash(&a);
+        a.play_session(&session, 70).unwrap();
+        assert_eq!(screen_hash(&a), hash);
+
+        let reseeded = Session {
+            seed: 0x4321,
+            ..session.clone()
+        };
+        b.play_session(&reseeded, 70).unwrap();
+        assert_ne!(screen_hash(&b), hash);
+    }
+
+    #[test]
+    fn test_play_session_timers() {
+        let session = session(
+            vec![
+                0x6A, 0x05, // VA = 5
+                0xFA, 0x15, // DT = VA
+                0xF0, 0x07, // V0 = DT
+                0x12, 0x04, // 循环
+            ],
+            KeyScript::new(),
+        );
+        let mut c8: Chip8<_> = Chip8::new(LinearCongruentialGenerator::default());
+        // 第 10, 20 次 tick 后各更新一次计时器
+        c8.play_session(&session, 23).unwrap();
+        assert_eq!(c8.delay_timer, 3);
+        assert_eq!(c8.v_reg[0], 3);
+    }
+}
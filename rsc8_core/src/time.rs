@@ -0,0 +1,6 @@
+pub const TIMER_HZ: u64 = 60;
+
+// 时间来源, 返回距上次调用经过的毫秒数
+pub trait TimeSource {
+    fn elapsed_ms(&mut self) -> u64;
+}
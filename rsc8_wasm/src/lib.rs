@@ -6,6 +6,9 @@ use rsc8_core::{
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, KeyboardEvent};
 
+// 不经过 wasm-bindgen 的 C ABI 入口 (emu_new, emu_tick 等), 直接出现在 wasm 的导出表中
+pub use rsc8_core::ffi::*;
+
 #[wasm_bindgen]
 pub struct Chip8Wasm {
     chip8: Chip8<LinearCongruentialGenerator>,
@@ -59,15 +62,17 @@ impl Chip8Wasm {
     }
 
     #[wasm_bindgen]
-    pub fn load_game(&mut self, data: Uint8Array) {
-        self.chip8.load_rom(&data.to_vec());
+    pub fn load_game(&mut self, data: Uint8Array) -> Result<(), JsValue> {
+        self.chip8
+            .load_rom(&data.to_vec())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
     }
 
     #[wasm_bindgen]
     pub fn draw_screen(&mut self, scale: usize) {
         let disp = self.chip8.get_display();
-        for i in 0..(SCREEN_WIDTH * SCREEN_HEIGHT) {
-            if disp[i] {
+        for (i, &pixel) in disp.iter().enumerate().take(SCREEN_WIDTH * SCREEN_HEIGHT) {
+            if pixel {
                 let x = i % SCREEN_WIDTH;
                 let y = i / SCREEN_WIDTH;
                 self.ctx.fill_rect(